@@ -0,0 +1 @@
+ALTER TABLE servers ADD COLUMN playback_policy INT NOT NULL DEFAULT 0;
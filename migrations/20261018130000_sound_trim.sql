@@ -0,0 +1,2 @@
+ALTER TABLE sounds ADD COLUMN trim_start_ms INT UNSIGNED NOT NULL DEFAULT 0;
+ALTER TABLE sounds ADD COLUMN trim_end_ms INT UNSIGNED;
@@ -0,0 +1 @@
+ALTER TABLE sounds ADD COLUMN loudness FLOAT;
@@ -0,0 +1,2 @@
+ALTER TABLE servers ADD COLUMN fade_in_ms INT UNSIGNED NOT NULL DEFAULT 0;
+ALTER TABLE servers ADD COLUMN fade_out_ms INT UNSIGNED NOT NULL DEFAULT 0;
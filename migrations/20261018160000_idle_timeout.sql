@@ -0,0 +1 @@
+ALTER TABLE servers ADD COLUMN idle_timeout INT UNSIGNED NOT NULL DEFAULT 0;
@@ -0,0 +1 @@
+ALTER TABLE servers ADD COLUMN max_duration INT UNSIGNED;
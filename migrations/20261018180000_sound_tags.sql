@@ -0,0 +1,14 @@
+CREATE TABLE tags (
+    id INT UNSIGNED NOT NULL AUTO_INCREMENT,
+    name VARCHAR(32) NOT NULL,
+    UNIQUE (name),
+    PRIMARY KEY (id)
+);
+
+CREATE TABLE sound_tags (
+    sound_id INT UNSIGNED NOT NULL,
+    tag_id INT UNSIGNED NOT NULL,
+    FOREIGN KEY (sound_id) REFERENCES `sounds`(`id`) ON DELETE CASCADE ON UPDATE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES `tags`(`id`) ON DELETE CASCADE ON UPDATE CASCADE,
+    PRIMARY KEY (sound_id, tag_id)
+);
@@ -0,0 +1,5 @@
+ALTER TABLE sounds ADD COLUMN created_at DATETIME;
+ALTER TABLE sounds ADD COLUMN duration_ms INT UNSIGNED;
+ALTER TABLE sounds ADD COLUMN size_bytes INT UNSIGNED;
+
+UPDATE sounds SET size_bytes = LENGTH(src);
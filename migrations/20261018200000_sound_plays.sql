@@ -0,0 +1,13 @@
+CREATE TABLE sound_plays (
+    id BIGINT UNSIGNED NOT NULL AUTO_INCREMENT,
+    sound_id INT UNSIGNED,
+    sound_name VARCHAR(20) NOT NULL,
+    guild_id BIGINT UNSIGNED NOT NULL,
+    user_id BIGINT UNSIGNED NOT NULL,
+    played_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    -- Plays outlive their sound, so the history still shows sounds that have been deleted
+    FOREIGN KEY (sound_id) REFERENCES `sounds`(`id`) ON DELETE SET NULL ON UPDATE CASCADE,
+    INDEX (guild_id, sound_id),
+    INDEX (user_id, sound_id, played_at),
+    PRIMARY KEY (id)
+);
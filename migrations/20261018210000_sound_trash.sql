@@ -0,0 +1,2 @@
+ALTER TABLE sounds ADD COLUMN deleted_at DATETIME;
+ALTER TABLE sounds ADD INDEX (deleted_at);
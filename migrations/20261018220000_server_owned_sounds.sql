@@ -0,0 +1 @@
+ALTER TABLE sounds MODIFY uploader_id BIGINT UNSIGNED;
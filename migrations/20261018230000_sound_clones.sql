@@ -0,0 +1,2 @@
+ALTER TABLE sounds ADD COLUMN cloned_from INT UNSIGNED;
+ALTER TABLE sounds ADD FOREIGN KEY (cloned_from) REFERENCES `sounds`(`id`) ON DELETE SET NULL ON UPDATE CASCADE;
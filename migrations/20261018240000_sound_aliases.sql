@@ -0,0 +1,7 @@
+CREATE TABLE sound_aliases (
+    sound_id INT UNSIGNED NOT NULL,
+    name VARCHAR(20) NOT NULL,
+    FOREIGN KEY (sound_id) REFERENCES `sounds`(`id`) ON DELETE CASCADE ON UPDATE CASCADE,
+    INDEX (name),
+    PRIMARY KEY (sound_id, name)
+);
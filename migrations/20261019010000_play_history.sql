@@ -0,0 +1,5 @@
+ALTER TABLE sound_plays
+    ADD COLUMN channel_id BIGINT UNSIGNED,
+    ADD COLUMN trigger_type ENUM('command', 'soundboard', 'greet', 'random') NOT NULL DEFAULT 'command',
+    ADD INDEX (guild_id, played_at),
+    ADD INDEX (played_at);
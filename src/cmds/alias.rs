@@ -0,0 +1,163 @@
+use poise::{serenity_prelude::CreateEmbed, CreateReply};
+
+use crate::{
+    cmds::{
+        autocomplete_managed_sound, autocomplete_sound, can_manage_sound,
+        manage::invalid_name_reason,
+    },
+    consts::THEME_COLOR,
+    models::sound::{Sound, SoundCtx},
+    Context, Error,
+};
+
+/// Manage alternative names of sounds
+#[poise::command(slash_command, rename = "alias", guild_only = true)]
+pub async fn alias(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Add an alternative name to a sound
+#[poise::command(slash_command, rename = "add", category = "Manage", guild_only = true)]
+pub async fn add_alias(
+    ctx: Context<'_>,
+    #[description = "Name or ID of sound to add an alias to"]
+    #[autocomplete = "autocomplete_managed_sound"]
+    name: String,
+    #[description = "Alternative name for the sound"] alias: String,
+) -> Result<(), Error> {
+    let sounds = ctx
+        .data()
+        .search_for_sound(&name, ctx.guild_id().unwrap(), ctx.author().id, true)
+        .await?;
+
+    match sounds.first() {
+        Some(sound) => {
+            // aliases share a namespace with the names of the uploader's sounds
+            let name_in_use = match sound.uploader_id {
+                Some(uploader_id) => {
+                    Sound::count_named_user_sounds(uploader_id, &alias, &ctx.data().database)
+                        .await?
+                        > 0
+                }
+                // Server-owned sounds only need to avoid their own names
+                None => {
+                    sound.name.to_lowercase() == alias.to_lowercase()
+                        || sound
+                            .aliases(&ctx.data().database)
+                            .await?
+                            .iter()
+                            .any(|existing| existing.to_lowercase() == alias.to_lowercase())
+                }
+            };
+
+            if !can_manage_sound(ctx, sound).await {
+                ctx.say("Only the uploader or server admins can add aliases to a sound.")
+                    .await?;
+            } else if let Some(reason) = invalid_name_reason(&alias) {
+                ctx.say(reason).await?;
+            } else if name_in_use {
+                ctx.say(
+                    "That name is already used by this sound or another of the uploader's sounds.",
+                )
+                .await?;
+            } else {
+                sound.add_alias(&alias, &ctx.data().database).await?;
+
+                ctx.say(format!(
+                    "Sound {} (ID {}) can now also be played as {}",
+                    sound.name, sound.id, alias
+                ))
+                .await?;
+            }
+        }
+
+        None => {
+            ctx.say("Sound could not be found by that name.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove an alternative name from a sound
+#[poise::command(
+    slash_command,
+    rename = "remove",
+    category = "Manage",
+    guild_only = true
+)]
+pub async fn remove_alias(
+    ctx: Context<'_>,
+    #[description = "Name or ID of sound to remove the alias from"]
+    #[autocomplete = "autocomplete_managed_sound"]
+    name: String,
+    #[description = "Alias to remove"] alias: String,
+) -> Result<(), Error> {
+    let sounds = ctx
+        .data()
+        .search_for_sound(&name, ctx.guild_id().unwrap(), ctx.author().id, true)
+        .await?;
+
+    match sounds.first() {
+        Some(sound) => {
+            if !can_manage_sound(ctx, sound).await {
+                ctx.say("Only the uploader or server admins can remove aliases from a sound.")
+                    .await?;
+            } else if sound.remove_alias(&alias, &ctx.data().database).await? {
+                ctx.say(format!(
+                    "Removed alias {} from sound {} (ID {})",
+                    alias, sound.name, sound.id
+                ))
+                .await?;
+            } else {
+                ctx.say("That sound doesn't have that alias.").await?;
+            }
+        }
+
+        None => {
+            ctx.say("Sound could not be found by that name.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Show the alternative names of a sound
+#[poise::command(slash_command, rename = "list", category = "Manage", guild_only = true)]
+pub async fn list_aliases(
+    ctx: Context<'_>,
+    #[description = "Name or ID of sound to show aliases of"]
+    #[autocomplete = "autocomplete_sound"]
+    name: String,
+) -> Result<(), Error> {
+    let sounds = ctx
+        .data()
+        .search_for_sound(&name, ctx.guild_id().unwrap(), ctx.author().id, true)
+        .await?;
+
+    match sounds.first() {
+        Some(sound) => {
+            let aliases = sound.aliases(&ctx.data().database).await?;
+
+            ctx.send(
+                CreateReply::default().embed(
+                    CreateEmbed::default()
+                        .color(THEME_COLOR)
+                        .title(format!("Aliases of {} (ID {})", sound.name, sound.id))
+                        .description(if aliases.is_empty() {
+                            "This sound has no aliases. Add some with `/alias add`".to_string()
+                        } else {
+                            aliases.join("\n")
+                        }),
+                ),
+            )
+            .await?;
+        }
+
+        None => {
+            ctx.say("Sound could not be found by that name.").await?;
+        }
+    }
+
+    Ok(())
+}
@@ -24,32 +24,54 @@ pub async fn help(ctx: Context<'_>) -> Result<(), Error> {
 *run these commands with no options*
 
 __Play Commands__
-`/play` - Play a sound by name or ID
+`/play` - Play a sound by name or ID, optionally with effects
 `/queue` - Play sounds on queue instead of instantly
+`/queued view` - View the sounds on queue
+`/queued skip/remove` - Skip the current sound or remove one from the queue
+`/queued shuffle/clear` - Shuffle or clear the queue
 `/loop` - Play a sound on loop
 `/disconnect` - Disconnect the bot
 `/stop` - Stop playback
+`/nowplaying` - Show the sound currently playing
+`/pause` `/resume` - Pause or resume the current sound
+`/seek` - Jump to a position in the current sound
 
 __Library Commands__
 `/upload` - Upload a sound file
 `/delete` - Delete a sound file
+`/trash list/restore` - View or restore deleted sounds
 `/download` - Download a sound file
+`/clone` - Copy a sound into your own library
+`/pack export/import` - Move sounds between servers as a zip archive
 `/public` - Set a sound as public/private
-`/list server` - List sounds on this server
+`/trim` - Set where a sound starts and ends
+`/rename` - Rename a sound
+`/replace` - Replace the audio of a sound
+`/transfer user/server` - Give a sound to another user or to this server
+`/tag add/remove` - Tag a sound, or remove a tag
+`/alias add/remove/list` - Manage alternative names for a sound
+`/list server` - List sounds on this server, optionally with a tag or sort order
 `/list user` - List your sounds
 `/favorites add` - Add a favorite
 `/favorites remove` - Remove a favorite
 `/list favorites` - List favorites
 
 __Search Commands__
-`/search` - Search for public sounds by name
+`/search` - Search for sounds by name. Include `#tag` to filter by tag, or filter by uploader, server, visibility, length or favorites
 `/random` - View random public sounds
+`/sound info` - Show details of a sound
+`/stats top` - Show the most played sounds on this server or globally
+`/history` - Show the latest sounds played on this server, optionally by one user
 
 __Setting Commands__
 `/greet server set/unset` - Set or unset a join sound for just this server
 `/greet user set/unset` - Set or unset a join sound across all servers
 `/greet enable/disable` - Enable or disable join sounds on this server
 `/volume` - Change the volume
+`/policy` - Overlay, interrupt or enqueue when a sound is already playing
+`/fade` - Fade sounds in when played and out when stopped
+`/idle` - Change how long the bot stays in a call after playing
+`/maxduration` - Limit how long sounds can play for
 
 __Advanced Commands__
 `/soundboard` - Create a soundboard",
@@ -1,17 +1,59 @@
+use log::warn;
 use poise::{
-    serenity_prelude::{Attachment, CreateAttachment, GuildId, RoleId},
+    serenity_prelude,
+    serenity_prelude::{Attachment, CreateAttachment, CreateEmbed, GuildId, RoleId, UserId},
     CreateReply,
 };
+use sqlx::Executor;
 
 #[cfg(feature = "metrics")]
 use crate::metrics::{DELETE_COUNTER, UPLOAD_COUNTER};
 use crate::{
-    cmds::autocomplete_sound,
-    consts::{MAX_SOUNDS, PATREON_GUILD, PATREON_ROLE},
+    cmds::{
+        autocomplete_managed_sound, autocomplete_sound, autocomplete_trashed, can_manage_sound,
+    },
+    consts::{MAX_SOUNDS, PATREON_GUILD, PATREON_ROLE, THEME_COLOR, TRASH_RETENTION_DAYS},
     models::sound::{Sound, SoundCtx},
-    Context, Error,
+    Context, Database, Error,
 };
 
+/// Check a name or alias can be given to a sound, returning the reason if not
+pub fn invalid_name_reason(name: &str) -> Option<&'static str> {
+    if name.is_empty() || name.len() > 20 {
+        Some("Please ensure the name provided is less than 20 characters in length")
+    } else if name.starts_with("@") {
+        Some("Sound names cannot start with an @ symbol. Please choose another name")
+    } else if name.starts_with("#") {
+        Some("Sound names cannot start with a # symbol, as it is used to search by tag. Please choose another name")
+    } else if name.chars().all(|c| c.is_digit(10)) {
+        Some("Please ensure the sound name contains a non-numerical character")
+    } else {
+        None
+    }
+}
+
+/// Whether a user can add another sound to their library, either because they are under the
+/// sound limit or because they are a Patreon supporter
+pub async fn within_sound_limit(
+    ctx: &serenity_prelude::Context,
+    db_pool: impl Executor<'_, Database = Database>,
+    user_id: UserId,
+) -> Result<bool, Error> {
+    // need to check how many sounds user currently has
+    let count = Sound::count_user_sounds(user_id, db_pool).await?;
+
+    // need to check if user is Patreon or not
+    if count >= *MAX_SOUNDS {
+        match GuildId::from(*PATREON_GUILD).member(ctx, user_id).await {
+            Ok(member) => Ok(member.roles.contains(&RoleId::from(*PATREON_ROLE))),
+
+            Err(_) => Ok(false),
+        }
+    } else {
+        Ok(true)
+    }
+}
+
 /// Upload a new sound to the bot
 #[poise::command(
     slash_command,
@@ -30,81 +72,133 @@ pub async fn upload_new_sound(
 
     ctx.defer().await?;
 
-    fn is_numeric(s: &String) -> bool {
-        for char in s.chars() {
-            if char.is_digit(10) {
-                continue;
+    if let Some(reason) = invalid_name_reason(&name) {
+        ctx.say(reason).await?;
+    } else {
+        // need to check the name is not currently in use by the user
+        let count_name =
+            Sound::count_named_user_sounds(ctx.author().id, &name, &ctx.data().database).await?;
+        if count_name > 0 {
+            ctx.say(
+                "You are already using that name. Please choose a unique name for your upload.",
+            )
+            .await?;
+        } else {
+            let permit_upload = within_sound_limit(
+                ctx.serenity_context(),
+                &ctx.data().database,
+                ctx.author().id,
+            )
+            .await?;
+
+            if permit_upload {
+                match Sound::create_anon(
+                    &name,
+                    file.url.as_str(),
+                    ctx.guild_id().unwrap(),
+                    ctx.author().id,
+                    &ctx.data().database,
+                )
+                .await
+                {
+                    Ok(_) => {
+                        ctx.say("Sound has been uploaded").await?;
+                    }
+
+                    Err(e) => {
+                        println!("Error occurred during upload: {:?}", e);
+                        ctx.say("Sound failed to upload.").await?;
+                    }
+                }
             } else {
-                return false;
+                ctx.say(format!(
+                        "You have reached the maximum number of sounds ({}). Either delete some with `/delete` or join our Patreon for unlimited uploads at **https://patreon.com/jellywx**",
+                        *MAX_SOUNDS,
+                    )).await?;
             }
         }
-        true
     }
 
-    if !name.is_empty() && name.len() <= 20 {
-        if name.starts_with("@") {
-            ctx.say("Sound names cannot start with an @ symbol. Please choose another name")
-                .await?;
-        } else if is_numeric(&name) {
-            ctx.say("Please ensure the sound name contains a non-numerical character")
+    Ok(())
+}
+
+/// Copy a sound into your own library
+#[poise::command(
+    slash_command,
+    rename = "clone",
+    category = "Manage",
+    guild_only = true
+)]
+pub async fn clone_sound(
+    ctx: Context<'_>,
+    #[description = "Name or ID of sound to copy"]
+    #[autocomplete = "autocomplete_sound"]
+    name: String,
+    #[description = "Name for your copy (default: same name)"] new_name: Option<String>,
+) -> Result<(), Error> {
+    let sounds = ctx
+        .data()
+        .search_for_sound(&name, ctx.guild_id().unwrap(), ctx.author().id, true)
+        .await?;
+
+    match sounds.first() {
+        Some(sound) if !sound.public && sound.uploader_id != Some(ctx.author().id.get()) => {
+            ctx.say("Only public sounds or your own sounds can be copied.")
                 .await?;
-        } else {
-            // need to check the name is not currently in use by the user
-            let count_name =
-                Sound::count_named_user_sounds(ctx.author().id, &name, &ctx.data().database)
-                    .await?;
-            if count_name > 0 {
+        }
+
+        Some(sound) => {
+            let new_name = new_name.unwrap_or_else(|| sound.name.clone());
+
+            // Aliases aren't copied, so only the new name can clash with the user's names and
+            // aliases
+            if let Some(reason) = invalid_name_reason(&new_name) {
+                ctx.say(reason).await?;
+            } else if Sound::count_named_user_sounds(
+                ctx.author().id,
+                &new_name,
+                &ctx.data().database,
+            )
+            .await?
+                > 0
+            {
                 ctx.say(
-                    "You are already using that name. Please choose a unique name for your upload.",
+                    "You are already using that name. Please choose a unique name for your copy.",
                 )
                 .await?;
+            } else if !within_sound_limit(
+                ctx.serenity_context(),
+                &ctx.data().database,
+                ctx.author().id,
+            )
+            .await?
+            {
+                ctx.say(format!(
+                    "You have reached the maximum number of sounds ({}). Either delete some with `/delete` or join our Patreon for unlimited uploads at **https://patreon.com/jellywx**",
+                    *MAX_SOUNDS,
+                ))
+                .await?;
             } else {
-                // need to check how many sounds user currently has
-                let count = Sound::count_user_sounds(ctx.author().id, &ctx.data().database).await?;
-                let mut permit_upload = true;
-
-                // need to check if user is Patreon or not
-                if count >= *MAX_SOUNDS {
-                    let patreon_guild_member = GuildId::from(*PATREON_GUILD)
-                        .member(ctx, ctx.author().id)
-                        .await;
-
-                    if let Ok(member) = patreon_guild_member {
-                        permit_upload = member.roles.contains(&RoleId::from(*PATREON_ROLE));
-                    } else {
-                        permit_upload = false;
-                    }
-                }
-
-                if permit_upload {
-                    match Sound::create_anon(
-                        &name,
-                        file.url.as_str(),
+                let id = sound
+                    .clone_to(
+                        &new_name,
                         ctx.guild_id().unwrap(),
                         ctx.author().id,
                         &ctx.data().database,
                     )
-                    .await
-                    {
-                        Ok(_) => {
-                            ctx.say("Sound has been uploaded").await?;
-                        }
+                    .await?;
 
-                        Err(e) => {
-                            println!("Error occurred during upload: {:?}", e);
-                            ctx.say("Sound failed to upload.").await?;
-                        }
-                    }
-                } else {
-                    ctx.say(format!(
-                            "You have reached the maximum number of sounds ({}). Either delete some with `/delete` or join our Patreon for unlimited uploads at **https://patreon.com/jellywx**",
-                            *MAX_SOUNDS,
-                        )).await?;
-                }
+                ctx.say(format!(
+                    "Copied {} (ID {}) to your sounds as {} (ID {})",
+                    sound.name, sound.id, new_name, id
+                ))
+                .await?;
             }
         }
-    } else {
-        ctx.say("Usage: `/upload <name>`. Please ensure the name provided is less than 20 characters in length").await?;
+
+        None => {
+            ctx.say("Sound could not be found by that name.").await?;
+        }
     }
 
     Ok(())
@@ -115,7 +209,7 @@ pub async fn upload_new_sound(
 pub async fn delete_sound(
     ctx: Context<'_>,
     #[description = "Name or ID of sound to delete"]
-    #[autocomplete = "autocomplete_sound"]
+    #[autocomplete = "autocomplete_managed_sound"]
     name: String,
 ) -> Result<(), Error> {
     #[cfg(feature = "metrics")]
@@ -148,9 +242,13 @@ pub async fn delete_sound(
                 };
 
                 if sound.uploader_id == Some(uid) || has_perms {
-                    sound.delete(&pool).await?;
+                    sound.trash(&pool).await?;
 
-                    ctx.say("Sound has been deleted").await?;
+                    ctx.say(format!(
+                        "Sound has been moved to the trash. It can be restored with `/trash restore` for {} days",
+                        *TRASH_RETENTION_DAYS
+                    ))
+                    .await?;
                 } else {
                     ctx.say("Only server admins can delete sounds uploaded by other users.")
                         .await?;
@@ -166,12 +264,252 @@ pub async fn delete_sound(
     Ok(())
 }
 
+/// Replace the audio of a sound, keeping its name and ID
+#[poise::command(slash_command, rename = "replace", guild_only = true)]
+pub async fn replace_sound(
+    ctx: Context<'_>,
+    #[description = "Name or ID of sound to replace"]
+    #[autocomplete = "autocomplete_managed_sound"]
+    name: String,
+    #[description = "New sound file (max. 2MB)"] file: Attachment,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+
+    let mut sound_vec = ctx
+        .data()
+        .search_for_sound(&name, ctx.guild_id().unwrap(), ctx.author().id, true)
+        .await?;
+    let sound_result = sound_vec.first_mut();
+
+    match sound_result {
+        Some(sound) => {
+            if !can_manage_sound(ctx, sound).await {
+                ctx.say("Only the uploader or server admins can replace a sound.")
+                    .await?;
+            } else {
+                match sound
+                    .replace_src(file.url.as_str(), &ctx.data().database)
+                    .await
+                {
+                    Ok(_) => {
+                        ctx.say(format!(
+                            "Sound {} (ID {}) has been replaced",
+                            sound.name, sound.id
+                        ))
+                        .await?;
+                    }
+
+                    Err(e) => {
+                        warn!("Error occurred during replace: {:?}", e);
+                        ctx.say("Sound failed to upload.").await?;
+                    }
+                }
+            }
+        }
+
+        None => {
+            ctx.say("Sound could not be found by that name.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Rename a sound
+#[poise::command(slash_command, rename = "rename", guild_only = true)]
+pub async fn rename_sound(
+    ctx: Context<'_>,
+    #[description = "Name or ID of sound to rename"]
+    #[autocomplete = "autocomplete_managed_sound"]
+    name: String,
+    #[description = "New name for the sound"] new_name: String,
+) -> Result<(), Error> {
+    let pool = ctx.data().database.clone();
+
+    let mut sound_vec = ctx
+        .data()
+        .search_for_sound(&name, ctx.guild_id().unwrap(), ctx.author().id, true)
+        .await?;
+    let sound_result = sound_vec.first_mut();
+
+    match sound_result {
+        Some(sound) => {
+            if !can_manage_sound(ctx, sound).await {
+                ctx.say("Only the uploader or server admins can rename a sound.")
+                    .await?;
+            } else if let Some(reason) = invalid_name_reason(&new_name) {
+                ctx.say(reason).await?;
+            } else {
+                // names only need to be unique among the uploader's sounds. Changing only the
+                // case of a name would otherwise conflict with the sound itself
+                let count_name = match sound.uploader_id {
+                    Some(_) if new_name.to_lowercase() == sound.name.to_lowercase() => 0,
+                    Some(uploader_id) => {
+                        Sound::count_named_user_sounds(uploader_id, &new_name, &pool).await?
+                    }
+                    None => 0,
+                };
+
+                if count_name > 0 {
+                    ctx.say("The uploader already has a sound with that name. Please choose a unique name.")
+                        .await?;
+                } else {
+                    let old_name = std::mem::replace(&mut sound.name, new_name);
+                    sound.commit(&pool).await?;
+
+                    ctx.say(format!(
+                        "Sound {} (ID {}) renamed to {}",
+                        old_name, sound.id, sound.name
+                    ))
+                    .await?;
+                }
+            }
+        }
+
+        None => {
+            ctx.say("Sound could not be found by that name.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Manage deleted sounds
+#[poise::command(slash_command, rename = "trash", guild_only = true)]
+pub async fn trash(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show deleted sounds from this server or that you uploaded
+#[poise::command(slash_command, rename = "list", category = "Manage", guild_only = true)]
+pub async fn list_trash(ctx: Context<'_>) -> Result<(), Error> {
+    let sounds = Sound::trashed(
+        ctx.guild_id().unwrap(),
+        ctx.author().id,
+        &ctx.data().database,
+    )
+    .await?;
+
+    let description = if sounds.is_empty() {
+        "The trash is empty.".to_string()
+    } else {
+        let mut lines = sounds
+            .iter()
+            .take(25)
+            .map(|sound| format!("**{}** (ID {})", sound.name, sound.id))
+            .collect::<Vec<_>>();
+
+        if sounds.len() > 25 {
+            lines.push(format!("*...and {} more*", sounds.len() - 25));
+        }
+
+        format!(
+            "Sounds are permanently deleted {} days after being trashed.\n\n{}",
+            *TRASH_RETENTION_DAYS,
+            lines.join("\n")
+        )
+    };
+
+    ctx.send(
+        CreateReply::default().ephemeral(true).embed(
+            CreateEmbed::default()
+                .color(THEME_COLOR)
+                .title("Trash")
+                .description(description),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Restore a deleted sound
+#[poise::command(
+    slash_command,
+    rename = "restore",
+    category = "Manage",
+    guild_only = true
+)]
+pub async fn restore_trash(
+    ctx: Context<'_>,
+    #[description = "Name or ID of sound to restore"]
+    #[autocomplete = "autocomplete_trashed"]
+    name: String,
+) -> Result<(), Error> {
+    let sounds = Sound::trashed(
+        ctx.guild_id().unwrap(),
+        ctx.author().id,
+        &ctx.data().database,
+    )
+    .await?;
+
+    let sound = sounds.iter().find(|sound| {
+        sound.id.to_string() == name
+            || format!("id:{}", sound.id) == name.to_lowercase()
+            || sound.name.to_lowercase() == name.to_lowercase()
+    });
+
+    match sound {
+        Some(sound) => {
+            let name_in_use = match sound.uploader_id {
+                Some(uploader_id) => {
+                    sound
+                        .conflicting_name(uploader_id, &ctx.data().database)
+                        .await?
+                }
+                None => None,
+            };
+
+            // Trashed sounds don't count towards the limit, so restoring one could exceed it
+            let within_limit = match sound.uploader_id {
+                Some(uploader_id) => {
+                    within_sound_limit(
+                        ctx.serenity_context(),
+                        &ctx.data().database,
+                        UserId::new(uploader_id),
+                    )
+                    .await?
+                }
+                None => true,
+            };
+
+            if !can_manage_sound(ctx, sound).await {
+                ctx.say("Only the uploader or server admins can restore a sound.")
+                    .await?;
+            } else if let Some(name) = name_in_use {
+                ctx.say(format!(
+                    "The uploader has another sound using the name {}. Rename it or remove the alias before restoring this sound.",
+                    name
+                ))
+                .await?;
+            } else if !within_limit {
+                ctx.say(format!(
+                    "The uploader has reached the maximum number of sounds ({}). Delete some with `/delete` before restoring this sound.",
+                    *MAX_SOUNDS,
+                ))
+                .await?;
+            } else {
+                sound.restore(&ctx.data().database).await?;
+
+                ctx.say(format!("Sound {} (ID {}) restored", sound.name, sound.id))
+                    .await?;
+            }
+        }
+
+        None => {
+            ctx.say("No sound in the trash by that name.").await?;
+        }
+    }
+
+    Ok(())
+}
+
 /// Change a sound between public and private
 #[poise::command(slash_command, rename = "public", guild_only = true)]
 pub async fn change_public(
     ctx: Context<'_>,
     #[description = "Name or ID of sound to change privacy setting of"]
-    #[autocomplete = "autocomplete_sound"]
+    #[autocomplete = "autocomplete_managed_sound"]
     name: String,
 ) -> Result<(), Error> {
     let pool = ctx.data().database.clone();
@@ -184,8 +522,8 @@ pub async fn change_public(
 
     match sound_result {
         Some(sound) => {
-            if sound.uploader_id != Some(uid) {
-                ctx.say("You can only change the visibility of sounds you have uploaded. Use `/list` to view your sounds").await?;
+            if !can_manage_sound(ctx, sound).await {
+                ctx.say("You can only change the visibility of sounds you have uploaded, or server sounds if you are an admin. Use `/list` to view your sounds").await?;
             } else {
                 if sound.public {
                     sound.public = false;
@@ -209,6 +547,72 @@ pub async fn change_public(
     Ok(())
 }
 
+/// Set where a sound starts and ends when played
+#[poise::command(slash_command, rename = "trim", guild_only = true)]
+pub async fn trim_sound(
+    ctx: Context<'_>,
+    #[description = "Name or ID of sound to trim"]
+    #[autocomplete = "autocomplete_managed_sound"]
+    name: String,
+    #[description = "Seconds to skip at the start of the sound (default: 0)"]
+    #[min = 0]
+    start: Option<f64>,
+    #[description = "Second at which the sound should stop (default: end of sound)"]
+    #[min = 0]
+    end: Option<f64>,
+) -> Result<(), Error> {
+    let pool = ctx.data().database.clone();
+
+    let uid = ctx.author().id.get();
+    let gid = ctx.guild_id().unwrap().get();
+
+    let mut sound_vec = ctx.data().search_for_sound(&name, gid, uid, true).await?;
+    let sound_result = sound_vec.first_mut();
+
+    match sound_result {
+        Some(sound) => {
+            let start_ms = (start.unwrap_or(0.0).max(0.0) * 1000.0) as u32;
+            let end_ms = end.map(|end| (end.max(0.0) * 1000.0) as u32);
+
+            if !can_manage_sound(ctx, sound).await {
+                ctx.say("You can only trim sounds you have uploaded, or server sounds if you are an admin. Use `/list` to view your sounds")
+                    .await?;
+            } else if end_ms.is_some_and(|end_ms| end_ms <= start_ms) {
+                ctx.say("The end of the sound must be after the start.")
+                    .await?;
+            } else if sound
+                .duration(&pool)
+                .await
+                .is_some_and(|duration| start_ms as u128 >= duration.as_millis())
+            {
+                ctx.say("The start must be before the end of the sound.")
+                    .await?;
+            } else {
+                sound.trim_start_ms = start_ms;
+                sound.trim_end_ms = end_ms;
+
+                sound.commit(&pool).await?;
+
+                ctx.say(format!(
+                    "Sound will now play from {}s to {}",
+                    start_ms as f64 / 1000.0,
+                    end_ms.map_or("the end".to_string(), |end_ms| format!(
+                        "{}s",
+                        end_ms as f64 / 1000.0
+                    ))
+                ))
+                .await?;
+            }
+        }
+
+        None => {
+            ctx.say("Sound could not be found by that name.").await?;
+        }
+    }
+
+    Ok(())
+}
+
 /// Download a sound file from the bot
 #[poise::command(slash_command, rename = "download", guild_only = true)]
 pub async fn download_file(
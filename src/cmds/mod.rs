@@ -1,14 +1,23 @@
-use poise::serenity_prelude::AutocompleteChoice;
+use poise::serenity_prelude::{AutocompleteChoice, GuildId, UserId};
 
-use crate::{models::sound::SoundCtx, Context};
+use crate::{
+    models::sound::{Sound, SoundCtx},
+    Context,
+};
 
+pub mod alias;
 pub mod favorite;
 pub mod info;
 pub mod manage;
+pub mod pack;
 pub mod play;
+pub mod queue;
 pub mod search;
 pub mod settings;
+pub mod stats;
 pub mod stop;
+pub mod tag;
+pub mod transfer;
 
 pub async fn autocomplete_sound(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice> {
     ctx.data()
@@ -16,7 +25,59 @@ pub async fn autocomplete_sound(ctx: Context<'_>, partial: &str) -> Vec<Autocomp
         .await
         .unwrap_or(vec![])
         .iter()
-        .map(|s| AutocompleteChoice::new(s.name.clone(), s.id.to_string()))
+        .map(|s| AutocompleteChoice::new(sound_label(ctx, s), s.id.to_string()))
+        .collect()
+}
+
+/// Autocomplete for commands that modify a sound, which only suggests sounds the author can
+/// manage
+pub async fn autocomplete_managed_sound(
+    ctx: Context<'_>,
+    partial: &str,
+) -> Vec<AutocompleteChoice> {
+    let guild_id = if manages_guild(ctx).await {
+        ctx.guild_id()
+    } else {
+        None
+    };
+
+    ctx.data()
+        .autocomplete_managed_sounds(&partial, ctx.author().id, guild_id)
+        .await
+        .unwrap_or(vec![])
+        .iter()
+        .map(|s| AutocompleteChoice::new(sound_label(ctx, s), s.id.to_string()))
+        .collect()
+}
+
+/// Name of a sound along with where it comes from, to tell apart sounds sharing a name
+fn sound_label(ctx: Context<'_>, sound: &Sound) -> String {
+    let origin = match sound.uploader_id {
+        Some(id) if id == ctx.author().id.get() => "yours".to_string(),
+
+        // Only cached names are used, as autocomplete has to respond quickly
+        Some(id) => ctx
+            .cache()
+            .user(UserId::new(id))
+            .map(|user| format!("by {}", user.name))
+            .unwrap_or_else(|| {
+                if sound.server_id == ctx.guild_id().unwrap().get() {
+                    "from this server".to_string()
+                } else {
+                    "public".to_string()
+                }
+            }),
+
+        None => ctx
+            .cache()
+            .guild(GuildId::new(sound.server_id))
+            .map(|guild| format!("from {}", guild.name))
+            .unwrap_or_else(|| "server sound".to_string()),
+    };
+
+    format!("{} — {} — #{}", sound.name, origin, sound.id)
+        .chars()
+        .take(100)
         .collect()
 }
 
@@ -29,3 +90,40 @@ pub async fn autocomplete_favorite(ctx: Context<'_>, partial: &str) -> Vec<Autoc
         .map(|s| AutocompleteChoice::new(s.name.clone(), s.id.to_string()))
         .collect()
 }
+
+pub async fn autocomplete_trashed(ctx: Context<'_>, partial: &str) -> Vec<AutocompleteChoice> {
+    Sound::trashed(
+        ctx.guild_id().unwrap(),
+        ctx.author().id,
+        &ctx.data().database,
+    )
+    .await
+    .unwrap_or(vec![])
+    .iter()
+    .filter(|s| s.name.to_lowercase().starts_with(&partial.to_lowercase()))
+    .take(25)
+    .map(|s| AutocompleteChoice::new(format!("{} — #{}", s.name, s.id), s.id.to_string()))
+    .collect()
+}
+
+/// Whether the author uploaded a sound, or is an admin of the server it belongs to
+pub async fn can_manage_sound(ctx: Context<'_>, sound: &Sound) -> bool {
+    let gid = ctx.guild_id().unwrap();
+
+    if sound.uploader_id == Some(ctx.author().id.get()) {
+        return true;
+    }
+
+    sound.server_id == gid.get() && manages_guild(ctx).await
+}
+
+/// Whether the author has permission to manage the server
+async fn manages_guild(ctx: Context<'_>) -> bool {
+    match ctx.guild_id().unwrap().member(&ctx, ctx.author().id).await {
+        Ok(member) => member
+            .permissions(&ctx)
+            .is_ok_and(|perms| perms.manage_guild()),
+
+        Err(_) => false,
+    }
+}
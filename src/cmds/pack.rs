@@ -0,0 +1,384 @@
+use std::io::{Cursor, Read, Write};
+
+use log::warn;
+use poise::{
+    serenity_prelude::{Attachment, CreateAttachment, CreateEmbed, PremiumTier},
+    CreateReply,
+};
+use serde::{Deserialize, Serialize};
+use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{
+    cmds::manage::{invalid_name_reason, within_sound_limit},
+    consts::{ATTACHMENT_MAX_SIZE, PACK_MAX_FILE_SIZE, PACK_MAX_SOUNDS, THEME_COLOR},
+    models::sound::{normalize_tag, Sound, SoundCtx, SoundSort},
+    Context, Error,
+};
+
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// Which sounds to put in a pack
+#[derive(Copy, Clone, poise::ChoiceParameter)]
+pub enum PackSource {
+    #[name = "Server"]
+    Server,
+    #[name = "User"]
+    User,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PackManifest {
+    version: u32,
+    sounds: Vec<PackEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PackEntry {
+    /// Name of the audio file in the archive
+    file: String,
+    name: String,
+    #[serde(default = "default_public")]
+    public: bool,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+fn default_public() -> bool {
+    true
+}
+
+/// Move sounds between servers as zip archives
+#[poise::command(slash_command, rename = "pack", guild_only = true)]
+pub async fn pack(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Download sounds as a zip archive that can be imported elsewhere
+#[poise::command(
+    slash_command,
+    rename = "export",
+    category = "Manage",
+    guild_only = true
+)]
+pub async fn export_pack(
+    ctx: Context<'_>,
+    #[description = "Sounds of this server, or your own sounds (default: Server)"] source: Option<
+        PackSource,
+    >,
+    #[description = "Only export sounds with this tag"]
+    #[max_length = 32]
+    tag: Option<String>,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+
+    let tag = tag.map(|tag| normalize_tag(&tag).unwrap_or(tag));
+    let sounds = match source.unwrap_or(PackSource::Server) {
+        PackSource::Server => {
+            ctx.data()
+                .guild_sounds(
+                    ctx.guild_id().unwrap(),
+                    None,
+                    tag.as_deref(),
+                    SoundSort::default(),
+                )
+                .await?
+        }
+        PackSource::User => {
+            ctx.data()
+                .user_sounds(ctx.author().id, None, tag.as_deref(), SoundSort::default())
+                .await?
+        }
+    };
+
+    if sounds.is_empty() {
+        ctx.say("There are no sounds to export.").await?;
+
+        return Ok(());
+    }
+
+    let max_size = attachment_limit(ctx);
+
+    let mut manifest = PackManifest {
+        version: 1,
+        sounds: vec![],
+    };
+    let mut files = vec![];
+    let mut size = 0;
+
+    for sound in &sounds {
+        let file = format!("{}-{}.opus", sound.id, sound.name.replace(['/', '\\'], "_"));
+        let src = sound.src(&ctx.data().database).await;
+
+        // Checked as the files are fetched, so an oversized pack isn't built only to be refused
+        size += src.len() as u64;
+        if size > max_size {
+            ctx.say(format!(
+                "These sounds are too large to send as one pack (limit {} MiB). Try exporting \
+                fewer sounds by using a tag.",
+                max_size / 1024 / 1024
+            ))
+            .await?;
+
+            return Ok(());
+        }
+
+        manifest.sounds.push(PackEntry {
+            file: file.clone(),
+            name: sound.name.clone(),
+            public: sound.public,
+            tags: sound.tags(&ctx.data().database).await?,
+        });
+        files.push((file, src));
+    }
+
+    let mut writer = ZipWriter::new(Cursor::new(vec![]));
+    // Opus is already compressed, so the files are stored as they are
+    let options = FileOptions::default().compression_method(CompressionMethod::Stored);
+
+    for (file, src) in files {
+        writer.start_file(file, options)?;
+        writer.write_all(&src)?;
+    }
+
+    writer.start_file(MANIFEST_NAME, options)?;
+    writer.write_all(&serde_json::to_vec_pretty(&manifest)?)?;
+
+    let archive = writer.finish()?.into_inner();
+
+    if archive.len() as u64 > max_size {
+        ctx.say(format!(
+            "These sounds are too large to send as one pack (limit {} MiB). Try exporting fewer \
+            sounds by using a tag.",
+            max_size / 1024 / 1024
+        ))
+        .await?;
+
+        return Ok(());
+    }
+
+    ctx.send(
+        CreateReply::default()
+            .content(format!("Exported {} sounds", sounds.len()))
+            .attachment(CreateAttachment::bytes(archive, "soundfx-pack.zip")),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Upload the sounds in a zip archive made by `/pack export`
+#[poise::command(
+    slash_command,
+    rename = "import",
+    category = "Manage",
+    default_member_permissions = "MANAGE_GUILD",
+    guild_only = true
+)]
+pub async fn import_pack(
+    ctx: Context<'_>,
+    #[description = "Pack file made by /pack export"] file: Attachment,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+
+    // Checked before downloading, so an oversized file is never held in memory
+    if file.size as u64 > PACK_MAX_SOUNDS as u64 * PACK_MAX_FILE_SIZE {
+        ctx.say(format!(
+            "That file is too large to be a pack. Packs hold at most {} sounds of up to {}MB each.",
+            PACK_MAX_SOUNDS,
+            PACK_MAX_FILE_SIZE / 1024 / 1024
+        ))
+        .await?;
+
+        return Ok(());
+    }
+
+    let archive = file.download().await?;
+    let (mut archive, manifest) = match tokio::task::spawn_blocking(|| read_pack(archive)).await? {
+        Ok(pack) => pack,
+
+        Err(e) => {
+            ctx.say(format!("Couldn't read that pack: {}", e)).await?;
+
+            return Ok(());
+        }
+    };
+
+    let mut results = vec![];
+
+    // Files are decompressed one at a time, so at most one is held in memory
+    for entry in &manifest.sounds {
+        let file = entry.file.clone();
+        let (returned, src) = tokio::task::spawn_blocking(move || {
+            let src = read_entry(&mut archive, &file);
+
+            (archive, src)
+        })
+        .await?;
+        archive = returned;
+
+        let result = match src {
+            Ok(src) => match import_entry(ctx, entry, &src).await {
+                Ok(result) => result,
+
+                // One sound failing to save shouldn't lose the report for the rest
+                Err(e) => {
+                    warn!("Error occurred during pack import: {:?}", e);
+
+                    Err("couldn't save sound".to_string())
+                }
+            },
+            Err(e) => Err(e),
+        };
+
+        results.push((entry, result));
+    }
+
+    let imported = results.iter().filter(|(_, result)| result.is_ok()).count();
+    let report = results
+        .iter()
+        .map(|(entry, result)| match result {
+            Ok(id) => format!("✅ **{}** (ID {})", entry.name, id),
+            Err(e) => format!("❌ **{}**: {}", entry.name, e),
+        })
+        .collect::<Vec<_>>();
+
+    let mut description = String::new();
+    for (index, line) in report.iter().enumerate() {
+        // Embed descriptions are limited to 4096 characters
+        if description.len() + line.len() > 4000 {
+            description += &format!("*...and {} more*", report.len() - index);
+            break;
+        }
+
+        description += line;
+        description += "\n";
+    }
+
+    ctx.send(
+        CreateReply::default().embed(
+            CreateEmbed::default()
+                .color(THEME_COLOR)
+                .title(format!("Imported {} of {} sounds", imported, report.len()))
+                .description(description),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Largest attachment that can be sent in the current guild
+fn attachment_limit(ctx: Context<'_>) -> u64 {
+    let tier = ctx.guild().map(|guild| guild.premium_tier);
+
+    match tier {
+        Some(PremiumTier::Tier3) => 100 * 1024 * 1024,
+        Some(PremiumTier::Tier2) => 50 * 1024 * 1024,
+        _ => ATTACHMENT_MAX_SIZE,
+    }
+}
+
+/// Open a pack and read its manifest
+fn read_pack(
+    archive: Vec<u8>,
+) -> Result<(ZipArchive<Cursor<Vec<u8>>>, PackManifest), Box<dyn std::error::Error + Send + Sync>> {
+    let mut archive = ZipArchive::new(Cursor::new(archive))?;
+
+    let manifest: PackManifest = {
+        let mut json = String::new();
+        archive
+            .by_name(MANIFEST_NAME)?
+            .take(PACK_MAX_FILE_SIZE)
+            .read_to_string(&mut json)?;
+
+        serde_json::from_str(&json)?
+    };
+
+    if manifest.sounds.len() > PACK_MAX_SOUNDS {
+        return Err(format!("packs can contain at most {} sounds", PACK_MAX_SOUNDS).into());
+    }
+
+    Ok((archive, manifest))
+}
+
+/// Decompress one sound's file from a pack
+fn read_entry(archive: &mut ZipArchive<Cursor<Vec<u8>>>, name: &str) -> Result<Vec<u8>, String> {
+    let file = archive
+        .by_name(name)
+        .map_err(|_| "file missing from pack".to_string())?;
+
+    if file.size() > PACK_MAX_FILE_SIZE {
+        return Err("file is too large".to_string());
+    }
+
+    // The size in the header can't be trusted, so reading stops just past the limit regardless
+    let mut src = vec![];
+    file.take(PACK_MAX_FILE_SIZE + 1)
+        .read_to_end(&mut src)
+        .map_err(|_| "couldn't read file".to_string())?;
+
+    if src.len() as u64 > PACK_MAX_FILE_SIZE {
+        return Err("file is too large".to_string());
+    }
+
+    Ok(src)
+}
+
+/// Upload one sound from a pack, returning its new ID or why it couldn't be uploaded
+async fn import_entry(
+    ctx: Context<'_>,
+    entry: &PackEntry,
+    src: &[u8],
+) -> Result<Result<u32, String>, Error> {
+    if let Some(reason) = invalid_name_reason(&entry.name) {
+        return Ok(Err(reason.to_string()));
+    }
+
+    if Sound::count_named_user_sounds(ctx.author().id, &entry.name, &ctx.data().database).await? > 0
+    {
+        return Ok(Err("you already have a sound with this name".to_string()));
+    }
+
+    if !within_sound_limit(
+        ctx.serenity_context(),
+        &ctx.data().database,
+        ctx.author().id,
+    )
+    .await?
+    {
+        return Ok(Err(
+            "you have reached the maximum number of sounds".to_string()
+        ));
+    }
+
+    let id = match Sound::create_from_bytes(
+        &entry.name,
+        src,
+        ctx.guild_id().unwrap(),
+        ctx.author().id,
+        &ctx.data().database,
+    )
+    .await
+    {
+        Ok(id) => id,
+
+        Err(e) => {
+            warn!("Error occurred during pack import: {:?}", e);
+
+            return Ok(Err("not a valid sound file".to_string()));
+        }
+    };
+
+    if let Some(mut sound) = Sound::from_id(id, &ctx.data().database).await? {
+        if !entry.public {
+            sound.public = false;
+            sound.commit(&ctx.data().database).await?;
+        }
+
+        for tag in entry.tags.iter().filter_map(|tag| normalize_tag(tag)) {
+            sound.add_tag(&tag, &ctx.data().database).await?;
+        }
+    }
+
+    Ok(Ok(id))
+}
@@ -11,8 +11,13 @@ use poise::{
 use crate::metrics::PLAY_COUNTER;
 use crate::{
     cmds::autocomplete_sound,
-    models::{guild_data::CtxGuildData, sound::SoundCtx},
-    utils::{join_channel, play_audio, play_from_query, queue_audio},
+    effects::Effects,
+    models::{
+        guild_data::CtxGuildData,
+        sound::{normalize_tag, SoundCtx, SoundSort},
+    },
+    playback::PlayTrigger,
+    utils::{join_channel, play_audio, play_from_query, queue_audio, PlayOptions},
     Context, Error,
 };
 
@@ -26,6 +31,17 @@ pub async fn play(
     #[description = "Channel to play in (default: your current voice channel)"]
     #[channel_types("Voice")]
     channel: Option<GuildChannel>,
+    #[description = "Playback speed, which also changes pitch (0.5 to 2)"]
+    #[min = 0.5]
+    #[max = 2]
+    speed: Option<f64>,
+    #[description = "Pitch shift in semitones (-12 to 12)"]
+    #[min = -12]
+    #[max = 12]
+    pitch: Option<i64>,
+    #[description = "Play the sound backwards"] reverse: Option<bool>,
+    #[description = "Add an echo"] echo: Option<bool>,
+    #[description = "Boost the bass"] bass_boost: Option<bool>,
 ) -> Result<(), Error> {
     #[cfg(feature = "metrics")]
     PLAY_COUNTER.inc();
@@ -42,7 +58,17 @@ pub async fn play(
             ctx.author().id,
             channel.map(|c| c.id),
             &name,
-            false,
+            PlayOptions {
+                r#loop: false,
+                effects: Effects {
+                    speed: speed.map(|s| s.clamp(0.5, 2.0)),
+                    pitch: pitch.map(|p| p.clamp(-12, 12)),
+                    reverse: reverse.unwrap_or(false),
+                    echo: echo.unwrap_or(false),
+                    bass_boost: bass_boost.unwrap_or(false),
+                },
+                trigger: PlayTrigger::Command,
+            },
         )
         .await,
     )
@@ -63,6 +89,9 @@ pub async fn play_random(
     #[description = "Channel to play in (default: your current voice channel)"]
     #[channel_types("Voice")]
     channel: Option<GuildChannel>,
+    #[description = "Only pick from sounds with this tag"]
+    #[max_length = 32]
+    tag: Option<String>,
 ) -> Result<(), Error> {
     ctx.defer().await?;
 
@@ -84,9 +113,21 @@ pub async fn play_random(
         Some(channel) => {
             let call = join_channel(ctx.serenity_context(), guild_id, channel).await?;
 
-            let sounds = ctx.data().guild_sounds(guild_id, None).await?;
+            // An invalid tag can't match any sounds, rather than being ignored
+            let tag = tag.map(|tag| normalize_tag(&tag).unwrap_or(tag));
+
+            let sounds = ctx
+                .data()
+                .guild_sounds(guild_id, None, tag.as_deref(), SoundSort::default())
+                .await?;
             if sounds.len() == 0 {
-                ctx.say("No sounds in this server!").await?;
+                match tag {
+                    Some(tag) => {
+                        ctx.say(format!("No sounds in this server tagged #{}!", tag))
+                            .await?
+                    }
+                    None => ctx.say("No sounds in this server!").await?,
+                };
                 return Ok(());
             }
 
@@ -98,21 +139,38 @@ pub async fn play_random(
             // anyway.
             match sounds.get(ts.subsec_micros() as usize % sounds.len()) {
                 Some(sound) => {
-                    let guild_data = ctx.data().guild_data(guild_id).await.unwrap();
-                    let mut lock = call.lock().await;
-
-                    play_audio(
+                    let volume = ctx
+                        .data()
+                        .guild_data(guild_id)
+                        .await
+                        .unwrap()
+                        .read()
+                        .await
+                        .volume;
+
+                    match play_audio(
                         sound,
-                        guild_data.read().await.volume,
-                        &mut lock,
-                        &ctx.data().database,
-                        false,
+                        volume,
+                        &call,
+                        ctx.data(),
+                        guild_id,
+                        ctx.author().id,
+                        PlayOptions {
+                            trigger: PlayTrigger::Random,
+                            ..Default::default()
+                        },
                     )
                     .await
-                    .unwrap();
-
-                    ctx.say(format!("Playing {} (ID {})", sound.name, sound.id))
-                        .await?;
+                    {
+                        Ok(_) => {
+                            ctx.say(format!("Playing {} (ID {})", sound.name, sound.id))
+                                .await?;
+                        }
+
+                        Err(e) => {
+                            ctx.say(format!("Couldn't play sound: {}", e)).await?;
+                        }
+                    }
                 }
 
                 None => {
@@ -232,7 +290,14 @@ pub async fn queue_play(
         Some(user_channel) => {
             let call = join_channel(ctx.serenity_context(), guild_id, user_channel).await?;
 
-            let guild_data = ctx.data().guild_data(guild_id).await.unwrap();
+            let volume = ctx
+                .data()
+                .guild_data(guild_id)
+                .await
+                .unwrap()
+                .read()
+                .await
+                .volume;
 
             let query_terms = [
                 Some(sound_1),
@@ -275,20 +340,24 @@ pub async fn queue_play(
                 }
             }
 
+            match queue_audio(
+                &sounds,
+                volume,
+                &call,
+                ctx.data(),
+                guild_id,
+                ctx.author().id,
+            )
+            .await
             {
-                let mut lock = call.lock().await;
-
-                queue_audio(
-                    &sounds,
-                    guild_data.read().await.volume,
-                    &mut lock,
-                    &ctx.data().database,
-                )
-                .await
-                .unwrap();
-            }
+                Ok(()) => {
+                    ctx.say(format!("Queued {} sounds!", sounds.len())).await?;
+                }
 
-            ctx.say(format!("Queued {} sounds!", sounds.len())).await?;
+                Err(e) => {
+                    ctx.say(format!("Couldn't queue sounds: {}", e)).await?;
+                }
+            }
         }
         None => {
             ctx.say("You are not in a voice chat!").await?;
@@ -310,6 +379,17 @@ pub async fn loop_play(
     #[description = "Name or ID of sound to loop"]
     #[autocomplete = "autocomplete_sound"]
     name: String,
+    #[description = "Playback speed, which also changes pitch (0.5 to 2)"]
+    #[min = 0.5]
+    #[max = 2]
+    speed: Option<f64>,
+    #[description = "Pitch shift in semitones (-12 to 12)"]
+    #[min = -12]
+    #[max = 12]
+    pitch: Option<i64>,
+    #[description = "Play the sound backwards"] reverse: Option<bool>,
+    #[description = "Add an echo"] echo: Option<bool>,
+    #[description = "Boost the bass"] bass_boost: Option<bool>,
 ) -> Result<(), Error> {
     ctx.defer().await?;
 
@@ -323,7 +403,17 @@ pub async fn loop_play(
             ctx.author().id,
             None,
             &name,
-            true,
+            PlayOptions {
+                r#loop: true,
+                effects: Effects {
+                    speed: speed.map(|s| s.clamp(0.5, 2.0)),
+                    pitch: pitch.map(|p| p.clamp(-12, 12)),
+                    reverse: reverse.unwrap_or(false),
+                    echo: echo.unwrap_or(false),
+                    bass_boost: bass_boost.unwrap_or(false),
+                },
+                trigger: PlayTrigger::Command,
+            },
         )
         .await,
     )
@@ -0,0 +1,187 @@
+use poise::{serenity_prelude::CreateEmbed, CreateReply};
+use rand::seq::SliceRandom;
+
+use crate::{consts::THEME_COLOR, playback::TrackMetadata, Context, Error};
+
+/// Manage the play queue. Sounds are added to it with `/queue`
+#[poise::command(
+    slash_command,
+    rename = "queued",
+    default_member_permissions = "SPEAK",
+    guild_only = true
+)]
+pub async fn queue(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// View the sounds currently on queue
+#[poise::command(slash_command, rename = "view", guild_only = true)]
+pub async fn view_queue(ctx: Context<'_>) -> Result<(), Error> {
+    let songbird = songbird::get(ctx.serenity_context()).await.unwrap();
+    let tracks = match songbird.get(ctx.guild_id().unwrap()) {
+        Some(call) => call.lock().await.queue().current_queue(),
+        None => vec![],
+    };
+
+    if tracks.is_empty() {
+        ctx.say("The queue is empty. Add sounds with `/queue`")
+            .await?;
+
+        return Ok(());
+    }
+
+    let mut lines = vec![];
+    for (position, handle) in tracks.iter().enumerate().take(25) {
+        let entry = match TrackMetadata::of(handle).await {
+            Some(metadata) => format!(
+                "**{}** (ID {}), queued by <@{}>",
+                metadata.sound.name, metadata.sound.id, metadata.requester
+            ),
+            None => "*Unknown sound*".to_string(),
+        };
+
+        if position == 0 {
+            lines.push(format!("▶ {}", entry));
+        } else {
+            lines.push(format!("`{}.` {}", position + 1, entry));
+        }
+    }
+
+    if tracks.len() > 25 {
+        lines.push(format!("*...and {} more*", tracks.len() - 25));
+    }
+
+    ctx.send(
+        CreateReply::default().embed(
+            CreateEmbed::default()
+                .color(THEME_COLOR)
+                .title("Queue")
+                .description(lines.join("\n")),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Skip the sound currently playing from the queue
+#[poise::command(slash_command, rename = "skip", guild_only = true)]
+pub async fn skip_queue(ctx: Context<'_>) -> Result<(), Error> {
+    let songbird = songbird::get(ctx.serenity_context()).await.unwrap();
+
+    let skipped = match songbird.get(ctx.guild_id().unwrap()) {
+        Some(call) => {
+            let lock = call.lock().await;
+
+            !lock.queue().is_empty() && lock.queue().skip().is_ok()
+        }
+
+        None => false,
+    };
+
+    if skipped {
+        ctx.say("⏭ Skipped").await?;
+    } else {
+        ctx.say("The queue is empty.").await?;
+    }
+
+    Ok(())
+}
+
+/// Remove a sound from the queue
+#[poise::command(slash_command, rename = "remove", guild_only = true)]
+pub async fn remove_queue(
+    ctx: Context<'_>,
+    #[description = "Position of the sound in the queue, as shown by `/queued view`"]
+    #[min = 1]
+    position: usize,
+) -> Result<(), Error> {
+    let songbird = songbird::get(ctx.serenity_context()).await.unwrap();
+    let call_opt = songbird.get(ctx.guild_id().unwrap());
+
+    let removed = match call_opt {
+        Some(call) => {
+            let lock = call.lock().await;
+            let queue = lock.queue();
+
+            if position == 1 {
+                // The head of the queue is playing, so skip it to start the next track
+                let current = queue.current();
+                let _ = queue.skip();
+
+                current
+            } else if position > 1 {
+                queue.dequeue(position - 1).map(|queued| {
+                    let _ = queued.stop();
+
+                    queued.handle()
+                })
+            } else {
+                None
+            }
+        }
+
+        None => None,
+    };
+
+    match removed {
+        Some(handle) => match TrackMetadata::of(&handle).await {
+            Some(metadata) => {
+                ctx.say(format!(
+                    "Removed {} (ID {}) from the queue",
+                    metadata.sound.name, metadata.sound.id
+                ))
+                .await?;
+            }
+            None => {
+                ctx.say("Removed sound from the queue").await?;
+            }
+        },
+
+        None => {
+            ctx.say("There is no sound at that position in the queue.")
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Shuffle the sounds waiting on the queue
+#[poise::command(slash_command, rename = "shuffle", guild_only = true)]
+pub async fn shuffle_queue(ctx: Context<'_>) -> Result<(), Error> {
+    let songbird = songbird::get(ctx.serenity_context()).await.unwrap();
+
+    if let Some(call) = songbird.get(ctx.guild_id().unwrap()) {
+        call.lock().await.queue().modify_queue(|queue| {
+            // Leave the playing track at the head of the queue
+            if queue.len() > 2 {
+                queue.make_contiguous()[1..].shuffle(&mut rand::thread_rng());
+            }
+        });
+    }
+
+    ctx.say("🔀 Queue shuffled").await?;
+
+    Ok(())
+}
+
+/// Remove all sounds waiting on the queue
+#[poise::command(slash_command, rename = "clear", guild_only = true)]
+pub async fn clear_queue(ctx: Context<'_>) -> Result<(), Error> {
+    let songbird = songbird::get(ctx.serenity_context()).await.unwrap();
+
+    if let Some(call) = songbird.get(ctx.guild_id().unwrap()) {
+        call.lock().await.queue().modify_queue(|queue| {
+            if queue.len() > 1 {
+                for queued in queue.drain(1..) {
+                    let _ = queued.stop();
+                }
+            }
+        });
+    }
+
+    ctx.say("Queue cleared").await?;
+
+    Ok(())
+}
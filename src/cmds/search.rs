@@ -1,36 +1,40 @@
+use std::time::{Duration, Instant};
+
 use poise::{
     serenity_prelude,
     serenity_prelude::{
-        constants::MESSAGE_CODE_LIMIT, ButtonStyle, ComponentInteraction, CreateActionRow,
-        CreateButton, CreateEmbed, EditInteractionResponse, GuildId, UserId,
+        ButtonStyle, ComponentInteraction, CreateActionRow, CreateButton, CreateEmbed,
+        EditInteractionResponse, GuildId, User, UserId,
     },
     CreateReply,
 };
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    consts::THEME_COLOR,
-    models::sound::{Sound, SoundCtx},
+    cmds::autocomplete_sound,
+    consts::{LIST_PAGE_SIZE, THEME_COLOR},
+    models::sound::{normalize_tag, SearchFilters, Sound, SoundCtx, SoundSort},
+    utils::format_duration,
     Context, Data, Error,
 };
 
-fn format_search_results(search_results: Vec<Sound>) -> CreateReply {
-    let builder = CreateReply::default();
-
-    let mut current_character_count = 0;
-    let title = "Public sounds matching filter:";
+/// How long the results of a `/search` are kept for its pager buttons
+const SEARCH_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
 
-    let field_iter = search_results
-        .iter()
-        .take(25)
-        .map(|item| (&item.name, format!("ID: {}", item.id), true))
-        .filter(|item| {
-            current_character_count += item.0.len() + item.1.len();
+/// Number of results on each page of a `/search`, leaving room for a play button each
+const SEARCH_PAGE_SIZE: u64 = 20;
 
-            current_character_count <= MESSAGE_CODE_LIMIT - title.len()
-        });
+/// A search whose results are being paged through
+pub struct SavedSearch {
+    /// IDs of the matching sounds, best match first
+    results: Vec<u32>,
+    created_at: Instant,
+}
 
-    builder.embed(CreateEmbed::default().title(title).fields(field_iter))
+/// Forget searches that can no longer be paged through
+fn prune_searches(data: &Data) {
+    data.search_cache
+        .retain(|_, search| search.created_at.elapsed() < SEARCH_CACHE_TTL);
 }
 
 /// Show uploaded sounds
@@ -44,6 +48,7 @@ enum ListContext {
     User = 0,
     Guild = 1,
     Favorite = 2,
+    Search = 3,
 }
 
 impl ListContext {
@@ -52,17 +57,34 @@ impl ListContext {
             ListContext::User => "Your sounds",
             ListContext::Favorite => "Your favorite sounds",
             ListContext::Guild => "Server sounds",
+            ListContext::Search => "Search results",
+        }
+    }
+
+    fn page_size(&self) -> u64 {
+        match self {
+            ListContext::Search => SEARCH_PAGE_SIZE,
+            _ => LIST_PAGE_SIZE,
         }
     }
 }
 
 /// Show the sounds uploaded to this server
 #[poise::command(slash_command, rename = "server", guild_only = true)]
-pub async fn list_guild_sounds(ctx: Context<'_>) -> Result<(), Error> {
+pub async fn list_guild_sounds(
+    ctx: Context<'_>,
+    #[description = "Only show sounds with this tag"]
+    #[max_length = 32]
+    tag: Option<String>,
+    #[description = "Order to list sounds in"] sort: Option<SoundSort>,
+) -> Result<(), Error> {
     let pager = SoundPager {
         nonce: 0,
         page: 0,
         context: ListContext::Guild,
+        tag: tag.map(|tag| normalize_tag(&tag).unwrap_or(tag)),
+        search: None,
+        sort: sort.unwrap_or_default(),
     };
 
     pager.reply(ctx).await?;
@@ -72,11 +94,20 @@ pub async fn list_guild_sounds(ctx: Context<'_>) -> Result<(), Error> {
 
 /// Show all sounds you have uploaded
 #[poise::command(slash_command, rename = "user", guild_only = true)]
-pub async fn list_user_sounds(ctx: Context<'_>) -> Result<(), Error> {
+pub async fn list_user_sounds(
+    ctx: Context<'_>,
+    #[description = "Only show sounds with this tag"]
+    #[max_length = 32]
+    tag: Option<String>,
+    #[description = "Order to list sounds in"] sort: Option<SoundSort>,
+) -> Result<(), Error> {
     let pager = SoundPager {
         nonce: 0,
         page: 0,
         context: ListContext::User,
+        tag: tag.map(|tag| normalize_tag(&tag).unwrap_or(tag)),
+        search: None,
+        sort: sort.unwrap_or_default(),
     };
 
     pager.reply(ctx).await?;
@@ -86,11 +117,20 @@ pub async fn list_user_sounds(ctx: Context<'_>) -> Result<(), Error> {
 
 /// Show sounds you have favorited
 #[poise::command(slash_command, rename = "favorite", guild_only = true)]
-pub async fn list_favorite_sounds(ctx: Context<'_>) -> Result<(), Error> {
+pub async fn list_favorite_sounds(
+    ctx: Context<'_>,
+    #[description = "Only show sounds with this tag"]
+    #[max_length = 32]
+    tag: Option<String>,
+    #[description = "Order to list sounds in"] sort: Option<SoundSort>,
+) -> Result<(), Error> {
     let pager = SoundPager {
         nonce: 0,
         page: 0,
         context: ListContext::Favorite,
+        tag: tag.map(|tag| normalize_tag(&tag).unwrap_or(tag)),
+        search: None,
+        sort: sort.unwrap_or_default(),
     };
 
     pager.reply(ctx).await?;
@@ -103,6 +143,16 @@ pub struct SoundPager {
     nonce: u64,
     page: u64,
     context: ListContext,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tag: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    search: Option<u64>,
+    #[serde(default, skip_serializing_if = "is_default_sort")]
+    sort: SoundSort,
+}
+
+fn is_default_sort(sort: &SoundSort) -> bool {
+    *sort == SoundSort::default()
 }
 
 impl SoundPager {
@@ -112,11 +162,84 @@ impl SoundPager {
         user_id: UserId,
         guild_id: GuildId,
     ) -> Result<Vec<Sound>, sqlx::Error> {
+        let tag = self.tag.as_deref();
+
+        match self.context {
+            ListContext::User => {
+                data.user_sounds(user_id, Some(self.page), tag, self.sort)
+                    .await
+            }
+            ListContext::Favorite => {
+                data.favorite_sounds(user_id, Some(self.page), tag, self.sort)
+                    .await
+            }
+            ListContext::Guild => {
+                data.guild_sounds(guild_id, Some(self.page), tag, self.sort)
+                    .await
+            }
+            ListContext::Search => {
+                let ids = self
+                    .search_results(data)
+                    .into_iter()
+                    .skip((self.page * SEARCH_PAGE_SIZE) as usize)
+                    .take(SEARCH_PAGE_SIZE as usize)
+                    .collect::<Vec<_>>();
+
+                Sound::from_ids(&ids, &data.database).await
+            }
+        }
+    }
+
+    async fn count(
+        &self,
+        data: &Data,
+        user_id: UserId,
+        guild_id: GuildId,
+    ) -> Result<u64, sqlx::Error> {
+        let tag = self.tag.as_deref();
+
         match self.context {
-            ListContext::User => data.user_sounds(user_id, Some(self.page)).await,
-            ListContext::Favorite => data.favorite_sounds(user_id, Some(self.page)).await,
-            ListContext::Guild => data.guild_sounds(guild_id, Some(self.page)).await,
+            ListContext::User => data.count_user_sounds(user_id, tag).await,
+            ListContext::Favorite => data.count_favorite_sounds(user_id, tag).await,
+            ListContext::Guild => data.count_guild_sounds(guild_id, tag).await,
+            ListContext::Search => Ok(self.search_results(data).len() as u64),
+        }
+    }
+
+    /// IDs of the sounds the search found. Once expired, the search shows no results rather
+    /// than dropping its filters
+    fn search_results(&self, data: &Data) -> Vec<u32> {
+        prune_searches(data);
+
+        self.search
+            .and_then(|id| data.search_cache.get(&id))
+            .map(|search| search.results.clone())
+            .unwrap_or_default()
+    }
+
+    fn max_page(&self, count: u64) -> u64 {
+        count.saturating_sub(1) / self.context.page_size()
+    }
+
+    fn components(&self, sounds: &[Sound], count: u64) -> Vec<CreateActionRow> {
+        let mut rows = vec![self.create_action_row(self.max_page(count))];
+
+        if let ListContext::Search = self.context {
+            rows.extend(sounds.chunks(5).map(|chunk| {
+                CreateActionRow::Buttons(
+                    chunk
+                        .iter()
+                        .map(|sound| {
+                            CreateButton::new(sound.id.to_string())
+                                .style(ButtonStyle::Secondary)
+                                .label(format!("▶️ {}", sound.name))
+                        })
+                        .collect(),
+                )
+            }));
         }
+
+        rows
     }
 
     fn create_action_row(&self, max_page: u64) -> CreateActionRow {
@@ -126,6 +249,9 @@ impl SoundPager {
                     nonce: 0,
                     page: 0,
                     context: self.context,
+                    tag: self.tag.clone(),
+                    search: self.search,
+                    sort: self.sort,
                 })
                 .unwrap(),
             )
@@ -137,6 +263,9 @@ impl SoundPager {
                     nonce: 1,
                     page: self.page.saturating_sub(1),
                     context: self.context,
+                    tag: self.tag.clone(),
+                    search: self.search,
+                    sort: self.sort,
                 })
                 .unwrap(),
             )
@@ -152,6 +281,9 @@ impl SoundPager {
                     nonce: 2,
                     page: self.page.saturating_add(1),
                     context: self.context,
+                    tag: self.tag.clone(),
+                    search: self.search,
+                    sort: self.sort,
                 })
                 .unwrap(),
             )
@@ -163,6 +295,9 @@ impl SoundPager {
                     nonce: 3,
                     page: max_page,
                     context: self.context,
+                    tag: self.tag.clone(),
+                    search: self.search,
+                    sort: self.sort,
                 })
                 .unwrap(),
             )
@@ -177,7 +312,10 @@ impl SoundPager {
     fn embed(&self, sounds: &[Sound], count: u64) -> CreateEmbed {
         CreateEmbed::default()
             .color(THEME_COLOR)
-            .title(self.context.title())
+            .title(match &self.tag {
+                Some(tag) => format!("{} tagged #{}", self.context.title(), tag),
+                None => self.context.title().to_string(),
+            })
             .description(format!("**{}** sounds:", count))
             .fields(sounds.iter().map(|s| {
                 (
@@ -202,18 +340,14 @@ impl SoundPager {
 
         let pager = serde_json::from_str::<Self>(&interaction.data.custom_id)?;
         let sounds = pager.get_page(data, user_id, guild_id).await?;
-        let count = match pager.context {
-            ListContext::User => data.count_user_sounds(user_id).await?,
-            ListContext::Favorite => data.count_favorite_sounds(user_id).await?,
-            ListContext::Guild => data.count_guild_sounds(guild_id).await?,
-        };
+        let count = pager.count(data, user_id, guild_id).await?;
 
         interaction
             .edit_response(
                 &ctx,
                 EditInteractionResponse::default()
                     .add_embed(pager.embed(&sounds, count))
-                    .components(vec![pager.create_action_row(count / 25)]),
+                    .components(pager.components(&sounds, count)),
             )
             .await?;
 
@@ -224,21 +358,15 @@ impl SoundPager {
         let sounds = self
             .get_page(ctx.data(), ctx.author().id, ctx.guild_id().unwrap())
             .await?;
-        let count = match self.context {
-            ListContext::User => ctx.data().count_user_sounds(ctx.author().id).await?,
-            ListContext::Favorite => ctx.data().count_favorite_sounds(ctx.author().id).await?,
-            ListContext::Guild => {
-                ctx.data()
-                    .count_guild_sounds(ctx.guild_id().unwrap())
-                    .await?
-            }
-        };
+        let count = self
+            .count(ctx.data(), ctx.author().id, ctx.guild_id().unwrap())
+            .await?;
 
         ctx.send(
             CreateReply::default()
                 .ephemeral(true)
                 .embed(self.embed(&sounds, count))
-                .components(vec![self.create_action_row(count / 25)]),
+                .components(self.components(&sounds, count)),
         )
         .await?;
 
@@ -246,6 +374,15 @@ impl SoundPager {
     }
 }
 
+/// Visibility of sounds to search for
+#[derive(Copy, Clone, poise::ChoiceParameter)]
+pub enum Visibility {
+    #[name = "Public"]
+    Public,
+    #[name = "Private"]
+    Private,
+}
+
 /// Search for sounds
 #[poise::command(
     slash_command,
@@ -255,14 +392,205 @@ impl SoundPager {
 )]
 pub async fn search_sounds(
     ctx: Context<'_>,
-    #[description = "Sound name to search for"] query: String,
+    #[description = "Sound name to search for"] query: Option<String>,
+    #[description = "Only show sounds uploaded by this user"] uploader: Option<User>,
+    #[description = "Only show sounds from the server with this ID"] server: Option<String>,
+    #[description = "Only show public or private sounds"] visibility: Option<Visibility>,
+    #[description = "Minimum length in seconds"]
+    #[min = 0]
+    min_duration: Option<f64>,
+    #[description = "Maximum length in seconds"]
+    #[min = 0]
+    max_duration: Option<f64>,
+    #[description = "Only show sounds you have favorited"] favorites_only: Option<bool>,
+) -> Result<(), Error> {
+    let server_id = match server.map(|server| server.trim().parse::<u64>()) {
+        Some(Ok(id)) => Some(id),
+        Some(Err(_)) => {
+            ctx.say("Server must be given as a server ID.").await?;
+
+            return Ok(());
+        }
+        None => None,
+    };
+
+    let filters = SearchFilters {
+        uploader_id: uploader.map(|user| user.id.get()),
+        server_id,
+        public: visibility.map(|visibility| matches!(visibility, Visibility::Public)),
+        min_duration_ms: min_duration.map(|secs| (secs * 1000.0) as u32),
+        max_duration_ms: max_duration.map(|secs| (secs * 1000.0) as u32),
+        favorites_only: favorites_only.unwrap_or(false),
+    };
+
+    let data = ctx.data();
+
+    // Searches are ranked once, and page turns only look up the sounds on that page
+    let results = data
+        .search_sounds(
+            &query.unwrap_or_default(),
+            ctx.guild_id().unwrap(),
+            ctx.author().id,
+            &filters,
+        )
+        .await?
+        .iter()
+        .map(|sound| sound.id)
+        .collect();
+
+    prune_searches(data);
+    data.search_cache.insert(
+        ctx.id(),
+        SavedSearch {
+            results,
+            created_at: Instant::now(),
+        },
+    );
+
+    let pager = SoundPager {
+        nonce: 0,
+        page: 0,
+        context: ListContext::Search,
+        tag: None,
+        search: Some(ctx.id()),
+        sort: SoundSort::default(),
+    };
+
+    pager.reply(ctx).await?;
+
+    Ok(())
+}
+
+/// View details of sounds
+#[poise::command(slash_command, rename = "sound", guild_only = true)]
+pub async fn sound(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show information about a sound
+#[poise::command(slash_command, rename = "info", category = "Search", guild_only = true)]
+pub async fn sound_info(
+    ctx: Context<'_>,
+    #[description = "Name or ID of sound to show"]
+    #[autocomplete = "autocomplete_sound"]
+    name: String,
 ) -> Result<(), Error> {
-    let search_results = ctx
+    let sounds = ctx
         .data()
-        .search_for_sound(&query, ctx.guild_id().unwrap(), ctx.author().id, false)
+        .search_for_sound(&name, ctx.guild_id().unwrap(), ctx.author().id, true)
         .await?;
 
-    ctx.send(format_search_results(search_results)).await?;
+    let sound = match sounds.first() {
+        Some(sound) => sound,
+
+        None => {
+            ctx.say("Sound could not be found by that name.").await?;
+
+            return Ok(());
+        }
+    };
+
+    let details = sound.details(&ctx.data().database).await?;
+    let tags = sound.tags(&ctx.data().database).await?;
+
+    let server_name = ctx
+        .serenity_context()
+        .cache
+        .guild(GuildId::new(sound.server_id))
+        .map(|guild| guild.name.clone());
+
+    let mut embed = CreateEmbed::default()
+        .color(THEME_COLOR)
+        .title(&sound.name)
+        .field("ID", sound.id.to_string(), true)
+        .field(
+            "Duration",
+            details.duration_ms.map_or("Unknown".to_string(), |ms| {
+                format_duration(Duration::from_millis(ms as u64))
+            }),
+            true,
+        )
+        .field(
+            "Size",
+            details.size_bytes.map_or("Unknown".to_string(), |bytes| {
+                format!("{:.1} KB", bytes as f64 / 1024.0)
+            }),
+            true,
+        )
+        .field(
+            "Format",
+            // Uploads are always converted to Opus when they are processed
+            match (details.size_bytes, details.duration_ms) {
+                (Some(bytes), Some(ms)) if ms > 0 => {
+                    format!("Opus, {} kbps", bytes as u64 * 8 / ms as u64)
+                }
+                _ => "Opus".to_string(),
+            },
+            true,
+        )
+        .field(
+            "Uploader",
+            sound
+                .uploader_id
+                .map_or("*Server*".to_string(), |id| format!("<@{}>", id)),
+            true,
+        )
+        .field(
+            "Server",
+            server_name.unwrap_or_else(|| sound.server_id.to_string()),
+            true,
+        )
+        .field(
+            "Visibility",
+            if sound.public {
+                "Public 🔓"
+            } else {
+                "Private 🔒"
+            },
+            true,
+        )
+        .field("Plays", details.plays.to_string(), true)
+        .field("Favorites", details.favorites.to_string(), true)
+        .field(
+            "Uploaded",
+            details
+                .created_at
+                .map_or("Unknown".to_string(), |ts| format!("<t:{}:D>", ts)),
+            true,
+        );
+
+    if sound.trim_start_ms > 0 || sound.trim_end_ms.is_some() {
+        embed = embed.field(
+            "Trim",
+            format!(
+                "{} - {}",
+                format_duration(Duration::from_millis(sound.trim_start_ms as u64)),
+                sound
+                    .trim_end_ms
+                    .map_or("end".to_string(), |ms| format_duration(
+                        Duration::from_millis(ms as u64)
+                    ))
+            ),
+            true,
+        );
+    }
+
+    if let Some(cloned_from) = details.cloned_from {
+        embed = embed.field("Cloned from", format!("ID {}", cloned_from), true);
+    }
+
+    if !tags.is_empty() {
+        embed = embed.field(
+            "Tags",
+            tags.iter()
+                .map(|tag| format!("`#{}`", tag))
+                .collect::<Vec<_>>()
+                .join(" "),
+            false,
+        );
+    }
+
+    ctx.send(CreateReply::default().embed(embed)).await?;
 
     Ok(())
 }
@@ -1,12 +1,12 @@
 use poise::{
     serenity_prelude::{GuildId, User},
-    CreateReply,
+    ChoiceParameter, CreateReply,
 };
 
 use crate::{
     cmds::autocomplete_sound,
     models::{
-        guild_data::{AllowGreet, CtxGuildData},
+        guild_data::{AllowGreet, CtxGuildData, PlaybackPolicy},
         join_sound::JoinSoundCtx,
         sound::SoundCtx,
     },
@@ -41,6 +41,189 @@ pub async fn change_volume(
     Ok(())
 }
 
+/// Change what happens when a sound is played while another is playing
+#[poise::command(
+    slash_command,
+    rename = "policy",
+    guild_only = true,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn change_playback_policy(
+    ctx: Context<'_>,
+    #[description = "Overlay sounds, interrupt the current sound, or enqueue behind it"]
+    policy: Option<PlaybackPolicy>,
+) -> Result<(), Error> {
+    let guild_data_opt = ctx.guild_data(ctx.guild_id().unwrap()).await;
+    let guild_data = guild_data_opt.unwrap();
+
+    if let Some(policy) = policy {
+        guild_data.write().await.playback_policy = policy;
+
+        guild_data.read().await.commit(&ctx.data().database).await?;
+
+        ctx.say(format!("Playback policy changed to {}", policy.name()))
+            .await?;
+    } else {
+        let read = guild_data.read().await;
+
+        ctx.say(format!(
+            "Current playback policy: {}. Change the policy with `/policy <new policy>`",
+            read.playback_policy.name()
+        ))
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Change how long sounds take to fade in when played and fade out when stopped
+#[poise::command(
+    slash_command,
+    rename = "fade",
+    guild_only = true,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn change_fade(
+    ctx: Context<'_>,
+    #[description = "Fade in duration in seconds (0 to disable)"]
+    #[min = 0]
+    #[max = 10]
+    fade_in: Option<f64>,
+    #[description = "Fade out duration in seconds (0 to disable)"]
+    #[min = 0]
+    #[max = 10]
+    fade_out: Option<f64>,
+) -> Result<(), Error> {
+    let guild_data_opt = ctx.guild_data(ctx.guild_id().unwrap()).await;
+    let guild_data = guild_data_opt.unwrap();
+
+    if fade_in.is_some() || fade_out.is_some() {
+        {
+            let mut write = guild_data.write().await;
+
+            if let Some(fade_in) = fade_in {
+                write.fade_in_ms = (fade_in.clamp(0.0, 10.0) * 1000.0) as u32;
+            }
+            if let Some(fade_out) = fade_out {
+                write.fade_out_ms = (fade_out.clamp(0.0, 10.0) * 1000.0) as u32;
+            }
+        }
+
+        guild_data.read().await.commit(&ctx.data().database).await?;
+    }
+
+    let read = guild_data.read().await;
+
+    ctx.say(format!(
+        "Fade in: {}s, fade out: {}s. Change these with `/fade <fade in> <fade out>`",
+        read.fade_in_ms as f64 / 1000.0,
+        read.fade_out_ms as f64 / 1000.0
+    ))
+    .await?;
+
+    Ok(())
+}
+
+/// Change how long the bot stays in a call after the last sound finishes
+#[poise::command(
+    slash_command,
+    rename = "idle",
+    guild_only = true,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn change_idle_timeout(
+    ctx: Context<'_>,
+    #[description = "Minutes to wait before leaving (0 to stay until everyone leaves)"]
+    #[max = 1440]
+    minutes: Option<u32>,
+) -> Result<(), Error> {
+    let guild_data_opt = ctx.guild_data(ctx.guild_id().unwrap()).await;
+    let guild_data = guild_data_opt.unwrap();
+
+    if let Some(minutes) = minutes {
+        guild_data.write().await.idle_timeout = minutes.min(1440) * 60;
+
+        guild_data.read().await.commit(&ctx.data().database).await?;
+
+        if minutes == 0 {
+            ctx.say("The bot will now stay in calls until everyone leaves")
+                .await?;
+        } else {
+            ctx.say(format!(
+                "The bot will now leave calls after {} minutes without playing",
+                minutes
+            ))
+            .await?;
+        }
+    } else {
+        let read = guild_data.read().await;
+
+        if read.idle_timeout == 0 {
+            ctx.say(
+                "The bot stays in calls until everyone leaves. Change this with `/idle <minutes>`",
+            )
+            .await?;
+        } else {
+            ctx.say(format!(
+                "The bot leaves calls after {} minutes without playing. Change this with `/idle <minutes>`",
+                read.idle_timeout / 60
+            ))
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Limit how long any sound can play for in this server
+#[poise::command(
+    slash_command,
+    rename = "maxduration",
+    guild_only = true,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn change_max_duration(
+    ctx: Context<'_>,
+    #[description = "Maximum seconds a sound can play for, including loops (0 for no limit)"]
+    seconds: Option<u32>,
+) -> Result<(), Error> {
+    let guild_data_opt = ctx.guild_data(ctx.guild_id().unwrap()).await;
+    let guild_data = guild_data_opt.unwrap();
+
+    if let Some(seconds) = seconds {
+        guild_data.write().await.max_duration = (seconds > 0).then_some(seconds);
+
+        guild_data.read().await.commit(&ctx.data().database).await?;
+
+        if seconds == 0 {
+            ctx.say("Sounds can now play for any length of time")
+                .await?;
+        } else {
+            ctx.say(format!("Sounds will now stop after {} seconds", seconds))
+                .await?;
+        }
+    } else {
+        let read = guild_data.read().await;
+
+        match read.max_duration {
+            Some(seconds) => {
+                ctx.say(format!(
+                    "Sounds stop after {} seconds. Change this with `/maxduration <seconds>`",
+                    seconds
+                ))
+                .await?;
+            }
+
+            None => {
+                ctx.say("Sounds can play for any length of time. Set a limit with `/maxduration <seconds>`")
+                    .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
 /// Manage greet sounds
 #[poise::command(slash_command, rename = "greet", guild_only = true)]
 pub async fn greet_sound(_ctx: Context<'_>) -> Result<(), Error> {
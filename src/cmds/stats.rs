@@ -0,0 +1,159 @@
+use poise::{
+    serenity_prelude::{ButtonStyle, CreateActionRow, CreateButton, CreateEmbed, User},
+    CreateReply,
+};
+
+use crate::{consts::THEME_COLOR, models::sound::Sound, Context, Error};
+
+/// Which plays a leaderboard counts
+#[derive(Copy, Clone, poise::ChoiceParameter)]
+pub enum StatsScope {
+    #[name = "Server"]
+    Server,
+    #[name = "Global"]
+    Global,
+}
+
+/// View sound statistics
+#[poise::command(slash_command, rename = "stats", guild_only = true)]
+pub async fn stats(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Show the most played sounds
+#[poise::command(slash_command, rename = "top", category = "Search", guild_only = true)]
+pub async fn top_sounds(
+    ctx: Context<'_>,
+    #[description = "Sounds played in this server, or public sounds played anywhere (default: Server)"]
+    scope: Option<StatsScope>,
+) -> Result<(), Error> {
+    let scope = scope.unwrap_or(StatsScope::Server);
+
+    let (title, top) = match scope {
+        StatsScope::Server => (
+            "Most played in this server",
+            Sound::top_in_guild(ctx.guild_id().unwrap(), &ctx.data().database).await?,
+        ),
+        StatsScope::Global => (
+            "Most played public sounds",
+            Sound::top_public(&ctx.data().database).await?,
+        ),
+    };
+
+    let description = if top.is_empty() {
+        "No sounds have been played yet.".to_string()
+    } else {
+        top.iter()
+            .enumerate()
+            .map(|(position, sound)| {
+                format!(
+                    "`{}.` **{}** (ID {}) - {} play{}",
+                    position + 1,
+                    sound.name,
+                    sound.id,
+                    sound.plays,
+                    if sound.plays == 1 { "" } else { "s" }
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    ctx.send(
+        CreateReply::default().embed(
+            CreateEmbed::default()
+                .color(THEME_COLOR)
+                .title(title)
+                .description(description),
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Show the latest sounds played in this server
+#[poise::command(
+    slash_command,
+    rename = "history",
+    category = "Search",
+    guild_only = true
+)]
+pub async fn play_history(
+    ctx: Context<'_>,
+    #[description = "Only show sounds played by this user"] user: Option<User>,
+) -> Result<(), Error> {
+    let plays = Sound::history(
+        ctx.guild_id().unwrap(),
+        user.as_ref().map(|user| user.id.get()),
+        &ctx.data().database,
+    )
+    .await?;
+
+    let description = if plays.is_empty() {
+        "No sounds have been played yet.".to_string()
+    } else {
+        plays
+            .iter()
+            .map(|play| {
+                format!(
+                    "<t:{}:R> **{}**{}{} - <@{}>{} via {}",
+                    play.played_at,
+                    play.name,
+                    play.sound_id
+                        .map_or(String::new(), |id| format!(" (ID {})", id)),
+                    if play.trashed { " *(deleted)*" } else { "" },
+                    play.user_id,
+                    play.channel_id
+                        .map_or(String::new(), |channel| format!(" in <#{}>", channel)),
+                    play.trigger_type
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    // Each sound gets one button, as buttons on a message need distinct IDs
+    let mut sound_ids = vec![];
+    let buttons = plays
+        .iter()
+        .filter(|play| !play.trashed)
+        .filter_map(|play| play.sound_id.map(|id| (id, &play.name)))
+        .filter(|(id, _)| {
+            if sound_ids.contains(id) {
+                false
+            } else {
+                sound_ids.push(*id);
+                true
+            }
+        })
+        .take(10)
+        .map(|(id, name)| {
+            CreateButton::new(id.to_string())
+                .style(ButtonStyle::Secondary)
+                .label(format!("🔁 {}", name))
+        })
+        .collect::<Vec<_>>();
+
+    ctx.send(
+        CreateReply::default()
+            .embed(
+                CreateEmbed::default()
+                    .color(THEME_COLOR)
+                    .title(match &user {
+                        Some(user) => format!("Recently played by {}", user.name),
+                        None => "Recently played in this server".to_string(),
+                    })
+                    .description(description),
+            )
+            .components(
+                buttons
+                    .chunks(5)
+                    .map(|chunk| CreateActionRow::Buttons(chunk.to_vec()))
+                    .collect(),
+            ),
+    )
+    .await?;
+
+    Ok(())
+}
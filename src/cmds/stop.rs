@@ -1,6 +1,13 @@
+use std::time::Duration;
+
+use poise::{serenity_prelude::CreateEmbed, CreateReply};
 use songbird;
 
-use crate::{Context, Error};
+use crate::{
+    consts::THEME_COLOR,
+    utils::{format_duration, stop_playback},
+    Context, Error,
+};
 
 /// Stop the bot from playing and clear the play queue
 #[poise::command(
@@ -10,16 +17,213 @@ use crate::{Context, Error};
     guild_only = true
 )]
 pub async fn stop_playing(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let now_playing = ctx.data().playback.now_playing(guild_id);
+
     let songbird = songbird::get(ctx.serenity_context()).await.unwrap();
-    let call_opt = songbird.get(ctx.guild_id().unwrap());
+    let call_opt = songbird.get(guild_id);
 
     if let Some(call) = call_opt {
-        let mut lock = call.lock().await;
+        stop_playback(&call, ctx.data(), guild_id).await?;
+    }
+
+    match now_playing {
+        Some(now_playing) => {
+            ctx.say(format!(
+                "👍 Stopped {} (ID {})",
+                now_playing.metadata.sound.name, now_playing.metadata.sound.id
+            ))
+            .await?;
+        }
 
-        lock.stop();
+        None => {
+            ctx.say("👍").await?;
+        }
     }
 
-    ctx.say("👍").await?;
+    Ok(())
+}
+
+/// Show the sound currently playing
+#[poise::command(
+    slash_command,
+    rename = "nowplaying",
+    default_member_permissions = "SPEAK",
+    guild_only = true
+)]
+pub async fn now_playing(ctx: Context<'_>) -> Result<(), Error> {
+    match ctx.data().playback.now_playing(ctx.guild_id().unwrap()) {
+        Some(now_playing) => {
+            let metadata = &now_playing.metadata;
+
+            let position = now_playing
+                .handle
+                .get_info()
+                .await
+                .map(|info| info.position)
+                .unwrap_or_else(|_| now_playing.started_at.elapsed());
+            let total = metadata
+                .sound
+                .duration(&ctx.data().database)
+                .await
+                .map(|duration| metadata.sound.trimmed_duration(duration));
+
+            // Sounds with effects are rendered with their trim applied, and may be sped up.
+            // Otherwise the track is the whole sound, skipped ahead to its start trim point
+            let (elapsed, total) = if metadata.effects.is_empty() {
+                (
+                    position
+                        .saturating_sub(Duration::from_millis(metadata.sound.trim_start_ms as u64)),
+                    total,
+                )
+            } else {
+                (
+                    position,
+                    total.map(|total| total.div_f64(metadata.effects.speed.unwrap_or(1.0))),
+                )
+            };
+
+            ctx.send(
+                CreateReply::default().embed(
+                    CreateEmbed::default()
+                        .color(THEME_COLOR)
+                        .title("Now playing")
+                        .description(format!(
+                            "**{}** (ID {}){}\nRequested by <@{}>\n`{} / {}`",
+                            metadata.sound.name,
+                            metadata.sound.id,
+                            if metadata.looping { " 🔁" } else { "" },
+                            metadata.requester,
+                            format_duration(elapsed),
+                            total.map_or("?".to_string(), format_duration),
+                        )),
+                ),
+            )
+            .await?;
+        }
+
+        None => {
+            ctx.say("Nothing is playing right now.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Pause the sound currently playing
+#[poise::command(
+    slash_command,
+    rename = "pause",
+    default_member_permissions = "SPEAK",
+    guild_only = true
+)]
+pub async fn pause_playing(ctx: Context<'_>) -> Result<(), Error> {
+    match ctx.data().playback.now_playing(ctx.guild_id().unwrap()) {
+        Some(now_playing) => match now_playing.handle.pause() {
+            Ok(()) => {
+                ctx.say("⏸ Paused. Use `/resume` to continue playing")
+                    .await?;
+            }
+
+            Err(e) => {
+                ctx.say(format!("Couldn't pause the sound: {}", e)).await?;
+            }
+        },
+
+        None => {
+            ctx.say("Nothing is playing right now.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Resume a paused sound
+#[poise::command(
+    slash_command,
+    rename = "resume",
+    default_member_permissions = "SPEAK",
+    guild_only = true
+)]
+pub async fn resume_playing(ctx: Context<'_>) -> Result<(), Error> {
+    match ctx.data().playback.now_playing(ctx.guild_id().unwrap()) {
+        Some(now_playing) => match now_playing.handle.play() {
+            Ok(()) => {
+                ctx.say("▶ Resumed").await?;
+            }
+
+            Err(e) => {
+                ctx.say(format!("Couldn't resume the sound: {}", e)).await?;
+            }
+        },
+
+        None => {
+            ctx.say("Nothing is playing right now.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Jump to a position in the sound currently playing
+#[poise::command(
+    slash_command,
+    rename = "seek",
+    default_member_permissions = "SPEAK",
+    guild_only = true
+)]
+pub async fn seek_playing(
+    ctx: Context<'_>,
+    #[description = "Position to jump to, in seconds from the start of the sound"]
+    #[min = 0]
+    seconds: f64,
+) -> Result<(), Error> {
+    ctx.defer().await?;
+
+    match ctx.data().playback.now_playing(ctx.guild_id().unwrap()) {
+        Some(now_playing) => {
+            let metadata = &now_playing.metadata;
+
+            // Sounds with effects are rendered with their trim applied. Otherwise the track is the
+            // whole sound, so positions are offset by its start trim point
+            let (trim_start, trim_end) = if metadata.effects.is_empty() {
+                (
+                    Duration::from_millis(metadata.sound.trim_start_ms as u64),
+                    metadata
+                        .sound
+                        .trim_end_ms
+                        .map(|end| Duration::from_millis(end as u64)),
+                )
+            } else {
+                (Duration::ZERO, None)
+            };
+            let target = trim_start + Duration::from_secs_f64(seconds.max(0.0));
+
+            if trim_end.is_some_and(|end| target >= end) {
+                ctx.say("That position is past the end of the sound.")
+                    .await?;
+            } else {
+                match now_playing.handle.seek_async(target).await {
+                    Ok(position) => {
+                        ctx.say(format!(
+                            "⏩ Jumped to {}",
+                            format_duration(position.saturating_sub(trim_start))
+                        ))
+                        .await?;
+                    }
+
+                    Err(e) => {
+                        ctx.say(format!("Couldn't seek in this sound: {}", e))
+                            .await?;
+                    }
+                }
+            }
+        }
+
+        None => {
+            ctx.say("Nothing is playing right now.").await?;
+        }
+    }
 
     Ok(())
 }
@@ -30,6 +234,8 @@ pub async fn disconnect(ctx: Context<'_>) -> Result<(), Error> {
     let songbird = songbird::get(ctx.serenity_context()).await.unwrap();
     let _ = songbird.leave(ctx.guild_id().unwrap()).await;
 
+    ctx.data().playback.clear(ctx.guild_id().unwrap());
+
     ctx.say("👍").await?;
 
     Ok(())
@@ -0,0 +1,121 @@
+use crate::{
+    cmds::{autocomplete_managed_sound, can_manage_sound},
+    consts::MAX_TAG_LENGTH,
+    models::sound::{normalize_tag, SoundCtx},
+    Context, Error,
+};
+
+/// Manage the tags of sounds
+#[poise::command(slash_command, rename = "tag", guild_only = true)]
+pub async fn tags(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Add a tag to a sound
+#[poise::command(slash_command, rename = "add", category = "Manage", guild_only = true)]
+pub async fn add_tag(
+    ctx: Context<'_>,
+    #[description = "Name or ID of sound to tag"]
+    #[autocomplete = "autocomplete_managed_sound"]
+    name: String,
+    #[description = "Tag to add"]
+    #[max_length = 32]
+    tag: String,
+) -> Result<(), Error> {
+    let tag = match normalize_tag(&tag) {
+        Some(tag) => tag,
+
+        None => {
+            ctx.say(format!(
+                "Tags must be at most {} characters, and only contain letters, numbers, `-` and `_`.",
+                MAX_TAG_LENGTH
+            ))
+            .await?;
+
+            return Ok(());
+        }
+    };
+
+    let sounds = ctx
+        .data()
+        .search_for_sound(&name, ctx.guild_id().unwrap(), ctx.author().id, true)
+        .await?;
+
+    match sounds.first() {
+        Some(sound) => {
+            if can_manage_sound(ctx, sound).await {
+                sound.add_tag(&tag, &ctx.data().database).await?;
+
+                ctx.say(format!(
+                    "Sound {} (ID {}) tagged with `#{}`",
+                    sound.name, sound.id, tag
+                ))
+                .await?;
+            } else {
+                ctx.say("Only the uploader or server admins can tag a sound.")
+                    .await?;
+            }
+        }
+
+        None => {
+            ctx.say("Sound could not be found by that name.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove a tag from a sound
+#[poise::command(
+    slash_command,
+    rename = "remove",
+    category = "Manage",
+    guild_only = true
+)]
+pub async fn remove_tag(
+    ctx: Context<'_>,
+    #[description = "Name or ID of sound to remove the tag from"]
+    #[autocomplete = "autocomplete_managed_sound"]
+    name: String,
+    #[description = "Tag to remove"]
+    #[max_length = 32]
+    tag: String,
+) -> Result<(), Error> {
+    let tag = normalize_tag(&tag);
+    let sounds = ctx
+        .data()
+        .search_for_sound(&name, ctx.guild_id().unwrap(), ctx.author().id, true)
+        .await?;
+
+    match sounds.first() {
+        Some(sound) => {
+            if !can_manage_sound(ctx, sound).await {
+                ctx.say("Only the uploader or server admins can untag a sound.")
+                    .await?;
+            } else {
+                let removed = match &tag {
+                    Some(tag) => sound.remove_tag(tag, &ctx.data().database).await?,
+                    None => false,
+                };
+
+                if removed {
+                    ctx.say(format!(
+                        "Removed tag `#{}` from sound {} (ID {})",
+                        tag.unwrap(),
+                        sound.name,
+                        sound.id
+                    ))
+                    .await?;
+                } else {
+                    ctx.say("That sound doesn't have that tag.").await?;
+                }
+            }
+        }
+
+        None => {
+            ctx.say("Sound could not be found by that name.").await?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,218 @@
+use poise::{
+    serenity_prelude,
+    serenity_prelude::{
+        ButtonStyle, ComponentInteraction, CreateActionRow, CreateButton,
+        CreateInteractionResponse, CreateInteractionResponseMessage, User,
+    },
+    CreateReply,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    cmds::{autocomplete_managed_sound, can_manage_sound, manage::within_sound_limit},
+    models::sound::{Sound, SoundCtx},
+    Context, Data, Error,
+};
+
+/// Give sounds to another user or to the server
+#[poise::command(slash_command, rename = "transfer", guild_only = true)]
+pub async fn transfer(_ctx: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Offer a sound to another user, who can accept or decline it
+#[poise::command(slash_command, rename = "user", category = "Manage", guild_only = true)]
+pub async fn transfer_to_user(
+    ctx: Context<'_>,
+    #[description = "Name or ID of sound to transfer"]
+    #[autocomplete = "autocomplete_managed_sound"]
+    name: String,
+    #[description = "User to transfer the sound to"] user: User,
+) -> Result<(), Error> {
+    let sounds = ctx
+        .data()
+        .search_for_sound(&name, ctx.guild_id().unwrap(), ctx.author().id, true)
+        .await?;
+
+    match sounds.first() {
+        Some(sound) => {
+            if !can_manage_sound(ctx, sound).await {
+                ctx.say("Only the uploader or server admins can transfer a sound.")
+                    .await?;
+            } else if user.bot {
+                ctx.say("Sounds can't be transferred to bots.").await?;
+            } else if sound.uploader_id == Some(user.id.get()) {
+                ctx.say("That user already owns this sound.").await?;
+            } else {
+                let prompt = |accept| TransferPrompt {
+                    sound: sound.id,
+                    from: sound.uploader_id,
+                    to: user.id.get(),
+                    accept,
+                };
+
+                ctx.send(
+                    CreateReply::default()
+                        .content(format!(
+                            "<@{}>, <@{}> wants to give you the sound **{}** (ID {}).",
+                            user.id,
+                            ctx.author().id,
+                            sound.name,
+                            sound.id
+                        ))
+                        .components(vec![CreateActionRow::Buttons(vec![
+                            CreateButton::new(serde_json::to_string(&prompt(true)).unwrap())
+                                .style(ButtonStyle::Success)
+                                .label("Accept"),
+                            CreateButton::new(serde_json::to_string(&prompt(false)).unwrap())
+                                .style(ButtonStyle::Danger)
+                                .label("Decline"),
+                        ])]),
+                )
+                .await?;
+            }
+        }
+
+        None => {
+            ctx.say("Sound could not be found by that name.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Make a sound owned by this server instead of its uploader
+#[poise::command(
+    slash_command,
+    rename = "server",
+    category = "Manage",
+    default_member_permissions = "MANAGE_GUILD",
+    guild_only = true
+)]
+pub async fn transfer_to_server(
+    ctx: Context<'_>,
+    #[description = "Name or ID of sound to transfer"]
+    #[autocomplete = "autocomplete_managed_sound"]
+    name: String,
+) -> Result<(), Error> {
+    let mut sounds = ctx
+        .data()
+        .search_for_sound(&name, ctx.guild_id().unwrap(), ctx.author().id, true)
+        .await?;
+
+    match sounds.first_mut() {
+        Some(sound) => {
+            let is_admin = match ctx.author_member().await {
+                Some(member) => member
+                    .permissions(&ctx)
+                    .is_ok_and(|perms| perms.manage_guild()),
+
+                None => false,
+            };
+
+            if sound.server_id != ctx.guild_id().unwrap().get() {
+                ctx.say("Only sounds uploaded to this server can be given to it.")
+                    .await?;
+            } else if !is_admin {
+                ctx.say("Only server admins can make a sound server-owned.")
+                    .await?;
+            } else if sound.uploader_id.is_none() {
+                ctx.say("That sound is already owned by the server.")
+                    .await?;
+            } else {
+                sound.uploader_id = None;
+                sound.commit(&ctx.data().database).await?;
+
+                ctx.say(format!(
+                    "Sound {} (ID {}) is now owned by this server",
+                    sound.name, sound.id
+                ))
+                .await?;
+            }
+        }
+
+        None => {
+            ctx.say("Sound could not be found by that name.").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Accept or decline button on a transfer offer
+#[derive(Serialize, Deserialize)]
+pub struct TransferPrompt {
+    sound: u32,
+    from: Option<u64>,
+    to: u64,
+    accept: bool,
+}
+
+impl TransferPrompt {
+    pub async fn handle_interaction(
+        ctx: &serenity_prelude::Context,
+        data: &Data,
+        interaction: &ComponentInteraction,
+    ) -> Result<(), Error> {
+        let prompt = serde_json::from_str::<Self>(&interaction.data.custom_id)?;
+
+        if interaction.user.id.get() != prompt.to {
+            interaction
+                .create_response(
+                    &ctx,
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new()
+                            .ephemeral(true)
+                            .content("This transfer isn't for you."),
+                    ),
+                )
+                .await?;
+
+            return Ok(());
+        }
+
+        let content = match Sound::from_id(prompt.sound, &data.database).await? {
+            // The sound may have changed hands since the offer was made
+            Some(sound) if sound.uploader_id != prompt.from => {
+                "This transfer is no longer valid.".to_string()
+            }
+
+            Some(_) if !prompt.accept => "Transfer declined.".to_string(),
+
+            Some(mut sound) => {
+                if let Some(name) = sound.conflicting_name(prompt.to, &data.database).await? {
+                    format!(
+                        "<@{}> already has a sound named {}. Rename one of them or remove the \
+                        alias, then try again.",
+                        prompt.to, name
+                    )
+                } else if !within_sound_limit(ctx, &data.database, interaction.user.id).await? {
+                    format!("<@{}> has reached the maximum number of sounds.", prompt.to)
+                } else {
+                    sound.uploader_id = Some(prompt.to);
+                    sound.commit(&data.database).await?;
+
+                    format!(
+                        "<@{}> now owns the sound {} (ID {}).",
+                        prompt.to, sound.name, sound.id
+                    )
+                }
+            }
+
+            None => "This sound no longer exists.".to_string(),
+        };
+
+        interaction
+            .create_response(
+                &ctx,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .content(content)
+                        .components(vec![]),
+                ),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
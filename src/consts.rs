@@ -2,6 +2,25 @@ use std::env;
 
 pub const THEME_COLOR: u32 = 0x00e0f3;
 
+/// Integrated loudness (LUFS) that sounds are normalized towards when played
+pub const TARGET_LOUDNESS: f32 = -16.0;
+/// Upper limit on the gain applied to quiet sounds, to avoid amplifying noise
+pub const MAX_LOUDNESS_GAIN: f32 = 4.0;
+
+/// Length limit of sound tags, matching the tags table
+pub const MAX_TAG_LENGTH: usize = 32;
+
+/// Sounds shown on each page of a listing, the most fields an embed can hold
+pub const LIST_PAGE_SIZE: u64 = 25;
+
+/// Largest attachment the bot can send in a server without boosts
+pub const ATTACHMENT_MAX_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Most sounds that can be imported from one pack
+pub const PACK_MAX_SOUNDS: usize = 100;
+/// Largest file in a pack that will be read, before it is converted and limited to the upload size
+pub const PACK_MAX_FILE_SIZE: u64 = 25 * 1024 * 1024;
+
 lazy_static! {
     pub static ref UPLOAD_MAX_SIZE: u64 = env::var("UPLOAD_MAX_SIZE")
         .unwrap_or_else(|_| "2097152".to_string())
@@ -11,6 +30,22 @@ lazy_static! {
         .unwrap_or_else(|_| "8".to_string())
         .parse::<u32>()
         .unwrap();
+    pub static ref TRASH_RETENTION_DAYS: u32 = env::var("TRASH_RETENTION_DAYS")
+        .unwrap_or_else(|_| "7".to_string())
+        .parse::<u32>()
+        .unwrap();
+    pub static ref PLAY_HISTORY_RETENTION_DAYS: u32 = env::var("PLAY_HISTORY_RETENTION_DAYS")
+        .unwrap_or_else(|_| "90".to_string())
+        .parse::<u32>()
+        .unwrap();
+    pub static ref CACHING_LOCATION: String =
+        env::var("CACHING_LOCATION").unwrap_or_else(|_| "/tmp".to_string());
+    /// Most space, in MiB, that cached effect renders may take up before the least recently
+    /// used are removed
+    pub static ref RENDER_CACHE_SIZE: u64 = env::var("RENDER_CACHE_SIZE")
+        .unwrap_or_else(|_| "512".to_string())
+        .parse::<u64>()
+        .unwrap();
     pub static ref PATREON_GUILD: u64 = env::var("PATREON_GUILD").unwrap().parse::<u64>().unwrap();
     pub static ref PATREON_ROLE: u64 = env::var("PATREON_ROLE").unwrap().parse::<u64>().unwrap();
 }
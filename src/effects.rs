@@ -0,0 +1,228 @@
+use std::{
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use log::warn;
+
+use crate::{consts::CACHING_LOCATION, ffmpeg};
+
+/// Audio effects that can be applied to a sound when it is played
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct Effects {
+    /// Playback speed multiplier. Changes pitch along with tempo
+    pub speed: Option<f64>,
+    /// Pitch shift in semitones, keeping the original tempo
+    pub pitch: Option<i64>,
+    pub reverse: bool,
+    pub echo: bool,
+    pub bass_boost: bool,
+}
+
+impl Effects {
+    pub fn is_empty(&self) -> bool {
+        *self == Effects::default()
+    }
+
+    /// ffmpeg filters that apply these effects to 48kHz audio
+    fn filters(&self) -> Vec<String> {
+        let mut filters = vec![];
+
+        if self.reverse {
+            filters.push("areverse".to_string());
+        }
+
+        if let Some(pitch) = self.pitch {
+            let factor = 2f64.powf(pitch as f64 / 12.0);
+
+            filters.push(format!(
+                "asetrate={},aresample=48000,atempo={}",
+                48000.0 * factor,
+                1.0 / factor
+            ));
+        }
+
+        if let Some(speed) = self.speed {
+            filters.push(format!("asetrate={},aresample=48000", 48000.0 * speed));
+        }
+
+        if self.bass_boost {
+            filters.push("bass=g=10".to_string());
+        }
+
+        if self.echo {
+            filters.push("aecho=0.8:0.88:60:0.4".to_string());
+        }
+
+        filters
+    }
+
+    /// Name identifying this combination of effects in cached file names
+    fn cache_key(&self) -> String {
+        let mut key = vec![];
+
+        if let Some(speed) = self.speed {
+            key.push(format!("speed{}", speed));
+        }
+        if let Some(pitch) = self.pitch {
+            key.push(format!("pitch{}", pitch));
+        }
+        if self.reverse {
+            key.push("reverse".to_string());
+        }
+        if self.echo {
+            key.push("echo".to_string());
+        }
+        if self.bass_boost {
+            key.push("bass".to_string());
+        }
+
+        key.join("-")
+    }
+
+    /// Render a sound with these effects, reusing a previous render of the same sound if cached.
+    /// Trim points are applied before the effects, as they refer to the original audio
+    pub async fn render(
+        &self,
+        sound_id: u32,
+        src: &[u8],
+        trim_start_ms: u32,
+        trim_end_ms: Option<u32>,
+    ) -> Option<Vec<u8>> {
+        let path = PathBuf::from(&*CACHING_LOCATION).join(format!(
+            "{}-{}-{}-{}.opus",
+            sound_id,
+            trim_start_ms,
+            trim_end_ms.map_or("end".to_string(), |end| end.to_string()),
+            self.cache_key()
+        ));
+
+        if let Ok(cached) = tokio::fs::read(&path).await {
+            // Renders are evicted by modification time, so mark this one as recently used
+            let touched = path.clone();
+            tokio::task::spawn_blocking(move || {
+                std::fs::File::options()
+                    .append(true)
+                    .open(&touched)
+                    .and_then(|file| file.set_modified(SystemTime::now()))
+            });
+
+            return Some(cached);
+        }
+
+        let mut filters = vec![format!(
+            "atrim=start={}{},asetpts=PTS-STARTPTS",
+            trim_start_ms as f64 / 1000.0,
+            trim_end_ms.map_or(String::new(), |end| format!(":end={}", end as f64 / 1000.0))
+        )];
+        filters.extend(self.filters());
+
+        let output = ffmpeg::pipe(
+            src,
+            &[
+                "-loglevel",
+                "error",
+                "-af",
+                &filters.join(","),
+                "-f",
+                "opus",
+                "pipe:1",
+            ],
+        )
+        .await?;
+
+        // Write to a temporary file first so that concurrent plays never read a partial render
+        let temp_path = path.with_extension(format!(
+            "{}.tmp",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos()
+        ));
+        if let Err(e) = async {
+            tokio::fs::write(&temp_path, &output.stdout).await?;
+            tokio::fs::rename(&temp_path, &path).await
+        }
+        .await
+        {
+            warn!("Couldn't cache rendered sound at {:?}: {:?}", path, e);
+        }
+
+        Some(output.stdout)
+    }
+}
+
+/// Whether a file in the cache is a render, as named by `Effects::render`
+fn is_render(file_name: &str) -> bool {
+    file_name.ends_with(".opus")
+        && file_name
+            .split('-')
+            .next()
+            .is_some_and(|id| id.parse::<u32>().is_ok())
+}
+
+/// Remove the least recently used renders until the cache takes up at most `max_bytes`
+pub async fn evict_renders(max_bytes: u64) {
+    let mut entries = match tokio::fs::read_dir(&*CACHING_LOCATION).await {
+        Ok(entries) => entries,
+
+        Err(e) => {
+            warn!("Couldn't read cache at {}: {:?}", *CACHING_LOCATION, e);
+            return;
+        }
+    };
+
+    let mut renders = vec![];
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if !is_render(&entry.file_name().to_string_lossy()) {
+            continue;
+        }
+
+        if let Ok(metadata) = entry.metadata().await {
+            renders.push((
+                metadata.modified().unwrap_or(UNIX_EPOCH),
+                metadata.len(),
+                entry.path(),
+            ));
+        }
+    }
+
+    let mut total = renders.iter().map(|(_, size, _)| size).sum::<u64>();
+    renders.sort_by_key(|(modified, _, _)| *modified);
+
+    for (_, size, path) in renders {
+        if total <= max_bytes {
+            break;
+        }
+
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => total -= size,
+            Err(e) => warn!("Couldn't remove cached render {:?}: {:?}", path, e),
+        }
+    }
+}
+
+/// Remove cached renders of a sound, so that they aren't played after its audio changes
+pub async fn clear_renders(sound_id: u32) {
+    let prefix = format!("{}-", sound_id);
+
+    let mut entries = match tokio::fs::read_dir(&*CACHING_LOCATION).await {
+        Ok(entries) => entries,
+
+        Err(e) => {
+            warn!("Couldn't read cache at {}: {:?}", *CACHING_LOCATION, e);
+            return;
+        }
+    };
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let file_name = entry.file_name();
+        let file_name = file_name.to_string_lossy();
+
+        if file_name.starts_with(&prefix) && is_render(&file_name) {
+            if let Err(e) = tokio::fs::remove_file(entry.path()).await {
+                warn!("Couldn't remove cached render {:?}: {:?}", entry.path(), e);
+            }
+        }
+    }
+}
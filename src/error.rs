@@ -3,11 +3,15 @@ use std::fmt::Formatter;
 #[derive(Debug)]
 pub enum ErrorTypes {
     InvalidFile,
+    RenderFailed,
 }
 
 impl std::error::Error for ErrorTypes {}
 impl std::fmt::Display for ErrorTypes {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "ErrorTypes: InvalidFile")
+        match self {
+            ErrorTypes::InvalidFile => write!(f, "ErrorTypes: InvalidFile"),
+            ErrorTypes::RenderFailed => write!(f, "ErrorTypes: RenderFailed"),
+        }
     }
 }
@@ -1,3 +1,4 @@
+use log::warn;
 use poise::serenity_prelude::{
     ActionRowComponent, ActivityData, ButtonKind, Context, CreateActionRow, CreateButton,
     EditInteractionResponse, FullEvent, Interaction,
@@ -6,13 +7,14 @@ use poise::serenity_prelude::{
 #[cfg(feature = "metrics")]
 use crate::metrics::GREET_COUNTER;
 use crate::{
-    cmds::search::SoundPager,
+    cmds::{search::SoundPager, transfer::TransferPrompt},
     models::{
         guild_data::{AllowGreet, CtxGuildData},
         join_sound::JoinSoundCtx,
         sound::Sound,
     },
-    utils::{join_channel, play_audio, play_from_query},
+    playback::PlayTrigger,
+    utils::{join_channel, play_audio, play_from_query, stop_playback, PlayOptions},
     Data, Error,
 };
 
@@ -37,6 +39,7 @@ pub async fn listener(ctx: &Context, event: &FullEvent, data: &Data) -> Result<(
                             let songbird = songbird::get(ctx).await.unwrap();
 
                             songbird.remove(guild_id).await?;
+                            data.playback.clear(guild_id);
                         }
                     }
                 }
@@ -63,32 +66,41 @@ pub async fn listener(ctx: &Context, event: &FullEvent, data: &Data) -> Result<(
                             )
                             .await
                         {
-                            let mut sound = sqlx::query_as_unchecked!(
+                            let sound = sqlx::query_as_unchecked!(
                                 Sound,
                                 "
-                                    SELECT name, id, public, server_id, uploader_id
+                                    SELECT name, id, public, server_id, uploader_id, trim_start_ms, trim_end_ms, loudness
                                         FROM sounds
-                                        WHERE id = ?",
+                                        WHERE id = ? AND deleted_at IS NULL",
                                 join_id
                             )
-                            .fetch_one(&data.database)
-                            .await
-                            .unwrap();
-
-                            let call = join_channel(&ctx, guild_id, user_channel).await?;
-
-                            #[cfg(feature = "metrics")]
-                            GREET_COUNTER.inc();
-
-                            play_audio(
-                                &mut sound,
-                                volume,
-                                &mut call.lock().await,
-                                &data.database,
-                                false,
-                            )
-                            .await
-                            .unwrap();
+                            .fetch_optional(&data.database)
+                            .await?;
+
+                            // Trashed sounds don't play as greets
+                            if let Some(mut sound) = sound {
+                                let call = join_channel(&ctx, guild_id, user_channel).await?;
+
+                                #[cfg(feature = "metrics")]
+                                GREET_COUNTER.inc();
+
+                                if let Err(e) = play_audio(
+                                    &mut sound,
+                                    volume,
+                                    &call,
+                                    data,
+                                    guild_id,
+                                    new.user_id,
+                                    PlayOptions {
+                                        trigger: PlayTrigger::Greet,
+                                        ..Default::default()
+                                    },
+                                )
+                                .await
+                                {
+                                    warn!("Couldn't play greet sound {}: {:?}", sound.id, e);
+                                }
+                            }
                         }
                     }
                 }
@@ -98,6 +110,9 @@ pub async fn listener(ctx: &Context, event: &FullEvent, data: &Data) -> Result<(
             Interaction::Component(component) => {
                 if let Some(guild_id) = component.guild_id {
                     if let Ok(()) = SoundPager::handle_interaction(ctx, &data, component).await {
+                    } else if let Ok(()) =
+                        TransferPrompt::handle_interaction(ctx, &data, component).await
+                    {
                     } else {
                         let mode = component.data.custom_id.as_str();
                         match mode {
@@ -108,9 +123,7 @@ pub async fn listener(ctx: &Context, event: &FullEvent, data: &Data) -> Result<(
                                 let call_opt = songbird.get(guild_id);
 
                                 if let Some(call) = call_opt {
-                                    let mut lock = call.lock().await;
-
-                                    lock.stop();
+                                    stop_playback(&call, data, guild_id).await?;
                                 }
                             }
 
@@ -184,7 +197,11 @@ pub async fn listener(ctx: &Context, event: &FullEvent, data: &Data) -> Result<(
                                     component.user.id,
                                     None,
                                     id.split('#').next().unwrap(),
-                                    mode == "loop",
+                                    PlayOptions {
+                                        r#loop: mode == "loop",
+                                        trigger: PlayTrigger::Soundboard,
+                                        ..Default::default()
+                                    },
                                 )
                                 .await;
                             }
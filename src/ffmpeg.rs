@@ -0,0 +1,31 @@
+use std::process::{Output, Stdio};
+
+use tokio::{io::AsyncWriteExt, process::Command};
+
+/// Run ffmpeg with `src` on its standard input
+pub async fn pipe(src: &[u8], args: &[&str]) -> Option<Output> {
+    let mut child = Command::new("ffmpeg")
+        .kill_on_drop(true)
+        .arg("-i")
+        .arg("pipe:0")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .ok()?;
+
+    // Feed the input from another task, as ffmpeg may block writing output before it has read
+    // all of it
+    let mut stdin = child.stdin.take()?;
+    let src = src.to_vec();
+    tokio::spawn(async move { stdin.write_all(&src).await });
+
+    let output = child.wait_with_output().await.ok()?;
+
+    if output.status.success() {
+        Some(output)
+    } else {
+        None
+    }
+}
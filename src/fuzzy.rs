@@ -0,0 +1,174 @@
+/// Lowest similarity at which a name is considered to match a search
+pub const MATCH_THRESHOLD: f32 = 0.5;
+
+/// How closely `name` matches `query`, from 0 (nothing in common) to 1 (identical). Tolerates
+/// typos through edit distance and shared bigrams
+pub fn similarity(query: &str, name: &str) -> f32 {
+    let query = query.to_lowercase();
+    let name = name.to_lowercase();
+
+    if query == name {
+        return 1.0;
+    }
+
+    let query_len = query.chars().count();
+    let name_len = name.chars().count();
+
+    if query_len == 0 || name_len == 0 {
+        return 0.0;
+    }
+
+    if name.contains(&query) {
+        // Substring matches rank above typos, and higher the more of the name they cover
+        return 0.8 + 0.2 * query_len as f32 / name_len as f32;
+    }
+
+    let edit = 1.0 - edit_distance(&query, &name) as f32 / query_len.max(name_len) as f32;
+
+    edit.max(bigram_similarity(&query, &name))
+}
+
+/// MySQL regular expression that matches names sharing at least one bigram with `query`, used to
+/// narrow down candidates before they are scored
+pub fn bigram_pattern(query: &str) -> String {
+    let chars = query.to_lowercase().chars().collect::<Vec<_>>();
+
+    if chars.len() < 2 {
+        return escape_regex(&chars.iter().collect::<String>());
+    }
+
+    let mut bigrams = chars
+        .windows(2)
+        .map(|pair| escape_regex(&pair.iter().collect::<String>()))
+        .collect::<Vec<_>>();
+    bigrams.dedup();
+
+    bigrams.join("|")
+}
+
+fn escape_regex(s: &str) -> String {
+    let mut escaped = String::new();
+
+    for c in s.chars() {
+        if "\\^$.|?*+()[]{}".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// Escape the wildcards of a MySQL `LIKE` pattern, so typed text is matched literally
+pub fn escape_like(s: &str) -> String {
+    let mut escaped = String::new();
+
+    for c in s.chars() {
+        if "\\%_".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// Sørensen–Dice coefficient of the bigrams of two strings
+fn bigram_similarity(a: &str, b: &str) -> f32 {
+    fn bigrams(s: &str) -> Vec<(char, char)> {
+        let chars = s.chars().collect::<Vec<_>>();
+
+        chars.windows(2).map(|pair| (pair[0], pair[1])).collect()
+    }
+
+    let a = bigrams(a);
+    let mut b = bigrams(b);
+
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    let total = a.len() + b.len();
+    let mut shared = 0;
+
+    for bigram in a {
+        if let Some(position) = b.iter().position(|other| *other == bigram) {
+            b.swap_remove(position);
+            shared += 1;
+        }
+    }
+
+    2.0 * shared as f32 / total as f32
+}
+
+/// Optimal string alignment distance: edits needed to turn one string into the other, counting
+/// swapping two adjacent characters as a single edit
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+
+            distances[i][j] = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distances[i][j] = distances[i][j].min(distances[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    distances[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transposed_letters_match() {
+        assert!(similarity("airhron", "airhorn") >= MATCH_THRESHOLD);
+        assert!(similarity("airhron", "airhorn") > similarity("airhron", "bruh"));
+    }
+
+    #[test]
+    fn substrings_rank_above_typos() {
+        assert!(similarity("horn", "airhorn") > similarity("airhron", "airhorn"));
+        assert_eq!(similarity("AirHorn", "airhorn"), 1.0);
+    }
+
+    #[test]
+    fn empty_and_short_queries() {
+        assert_eq!(similarity("", "airhorn"), 0.0);
+        assert_eq!(bigram_pattern(""), "");
+        assert_eq!(bigram_pattern("a"), "a");
+        assert_eq!(bigram_pattern("."), "\\.");
+        assert!(similarity("a", "airhorn") >= MATCH_THRESHOLD);
+        assert!(similarity("z", "airhorn") < MATCH_THRESHOLD);
+    }
+
+    #[test]
+    fn regex_characters_are_escaped() {
+        assert_eq!(escape_regex("a.b*(c)"), "a\\.b\\*\\(c\\)");
+        assert_eq!(bigram_pattern("a+b"), "a\\+|\\+b");
+        assert_eq!(bigram_pattern("aaa"), "aa");
+    }
+
+    #[test]
+    fn like_characters_are_escaped() {
+        assert_eq!(escape_like("100%_\\"), "100\\%\\_\\\\");
+        assert_eq!(escape_like("airhorn"), "airhorn");
+    }
+}
@@ -3,16 +3,21 @@ extern crate lazy_static;
 
 mod cmds;
 mod consts;
+mod effects;
 mod error;
 mod event_handlers;
+mod ffmpeg;
+mod fuzzy;
 #[cfg(feature = "metrics")]
 mod metrics;
 mod models;
+mod playback;
 mod utils;
 
-use std::{env, path::Path, sync::Arc};
+use std::{env, path::Path, sync::Arc, time::Duration};
 
 use dashmap::DashMap;
+use log::{info, warn};
 use poise::serenity_prelude::{
     model::{
         gateway::GatewayIntents,
@@ -20,11 +25,17 @@ use poise::serenity_prelude::{
     },
     ClientBuilder,
 };
-use songbird::SerenityInit;
+use songbird::{SerenityInit, Songbird};
 use sqlx::{MySql, Pool};
 use tokio::sync::RwLock;
 
-use crate::{event_handlers::listener, models::guild_data::GuildData};
+use crate::{
+    cmds::search::SavedSearch,
+    consts::{PLAY_HISTORY_RETENTION_DAYS, RENDER_CACHE_SIZE, TRASH_RETENTION_DAYS},
+    event_handlers::listener,
+    models::{guild_data::GuildData, sound::Sound},
+    playback::PlaybackRegistry,
+};
 
 type Database = MySql;
 
@@ -32,6 +43,8 @@ pub struct Data {
     database: Pool<Database>,
     guild_data_cache: DashMap<GuildId, Arc<RwLock<GuildData>>>,
     join_sound_cache: DashMap<UserId, DashMap<Option<GuildId>, Option<u32>>>,
+    playback: PlaybackRegistry,
+    search_cache: DashMap<u64, SavedSearch>,
 }
 
 type Error = Box<dyn std::error::Error + Send + Sync>;
@@ -55,9 +68,38 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
             cmds::manage::upload_new_sound(),
             cmds::manage::download_file(),
             cmds::manage::delete_sound(),
+            cmds::manage::trim_sound(),
+            cmds::manage::rename_sound(),
+            cmds::manage::replace_sound(),
+            cmds::manage::clone_sound(),
+            poise::Command {
+                subcommands: vec![cmds::manage::list_trash(), cmds::manage::restore_trash()],
+                ..cmds::manage::trash()
+            },
+            poise::Command {
+                subcommands: vec![cmds::pack::export_pack(), cmds::pack::import_pack()],
+                ..cmds::pack::pack()
+            },
+            poise::Command {
+                subcommands: vec![
+                    cmds::transfer::transfer_to_user(),
+                    cmds::transfer::transfer_to_server(),
+                ],
+                ..cmds::transfer::transfer()
+            },
             cmds::play::play(),
             cmds::play::play_random(),
             cmds::play::queue_play(),
+            poise::Command {
+                subcommands: vec![
+                    cmds::queue::view_queue(),
+                    cmds::queue::skip_queue(),
+                    cmds::queue::remove_queue(),
+                    cmds::queue::shuffle_queue(),
+                    cmds::queue::clear_queue(),
+                ],
+                ..cmds::queue::queue()
+            },
             cmds::play::loop_play(),
             cmds::play::soundboard(),
             poise::Command {
@@ -75,10 +117,39 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
                 ],
                 ..cmds::favorite::favorites()
             },
+            poise::Command {
+                subcommands: vec![
+                    cmds::alias::add_alias(),
+                    cmds::alias::remove_alias(),
+                    cmds::alias::list_aliases(),
+                ],
+                ..cmds::alias::alias()
+            },
+            poise::Command {
+                subcommands: vec![cmds::tag::add_tag(), cmds::tag::remove_tag()],
+                ..cmds::tag::tags()
+            },
             cmds::search::search_sounds(),
+            poise::Command {
+                subcommands: vec![cmds::search::sound_info()],
+                ..cmds::search::sound()
+            },
+            poise::Command {
+                subcommands: vec![cmds::stats::top_sounds()],
+                ..cmds::stats::stats()
+            },
+            cmds::stats::play_history(),
             cmds::stop::stop_playing(),
+            cmds::stop::now_playing(),
+            cmds::stop::pause_playing(),
+            cmds::stop::resume_playing(),
+            cmds::stop::seek_playing(),
             cmds::stop::disconnect(),
             cmds::settings::change_volume(),
+            cmds::settings::change_playback_policy(),
+            cmds::settings::change_fade(),
+            cmds::settings::change_idle_timeout(),
+            cmds::settings::change_max_duration(),
             poise::Command {
                 subcommands: vec![
                     poise::Command {
@@ -113,12 +184,53 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
 
     sqlx::migrate!().run(&database).await?;
 
+    {
+        let database = database.clone();
+        tokio::spawn(async move {
+            if let Err(e) = Sound::backfill_loudness(&database).await {
+                warn!("Loudness backfill failed: {:?}", e);
+            }
+
+            if let Err(e) = Sound::backfill_duration(&database).await {
+                warn!("Duration backfill failed: {:?}", e);
+            }
+        });
+    }
+
+    {
+        let database = database.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(60 * 60));
+
+            loop {
+                interval.tick().await;
+
+                match Sound::purge_trash(*TRASH_RETENTION_DAYS, &database).await {
+                    Ok(0) => {}
+                    Ok(purged) => info!("Purged {} sounds from the trash", purged),
+                    Err(e) => warn!("Trash purge failed: {:?}", e),
+                }
+
+                match Sound::purge_plays(*PLAY_HISTORY_RETENTION_DAYS, &database).await {
+                    Ok(0) => {}
+                    Ok(purged) => info!("Purged {} plays from the play history", purged),
+                    Err(e) => warn!("Play history purge failed: {:?}", e),
+                }
+
+                effects::evict_renders(*RENDER_CACHE_SIZE * 1024 * 1024).await;
+            }
+        });
+    }
+
     #[cfg(feature = "metrics")]
     {
         metrics::init_metrics();
         tokio::spawn(async { metrics::serve().await });
     }
 
+    let songbird = Songbird::serenity();
+    let playback = PlaybackRegistry::new(songbird.clone(), database.clone());
+
     let framework = poise::Framework::builder()
         .setup(move |ctx, _bot, framework| {
             Box::pin(async move {
@@ -128,6 +240,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
                     database,
                     guild_data_cache: Default::default(),
                     join_sound_cache: Default::default(),
+                    playback,
+                    search_cache: Default::default(),
                 })
             })
         })
@@ -139,7 +253,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         GatewayIntents::GUILD_VOICE_STATES | GatewayIntents::GUILDS,
     )
     .framework(framework)
-    .register_songbird()
+    .register_songbird_with(songbird)
     .await?;
 
     client.start_autosharded().await.unwrap();
@@ -14,6 +14,18 @@ pub enum AllowGreet {
     Disabled = -1,
 }
 
+/// What happens when a sound is played while another is already playing
+#[derive(Copy, Clone, Type, PartialEq, poise::ChoiceParameter)]
+#[repr(i32)]
+pub enum PlaybackPolicy {
+    #[name = "Overlay"]
+    Overlay = 0,
+    #[name = "Interrupt"]
+    Interrupt = 1,
+    #[name = "Enqueue"]
+    Enqueue = 2,
+}
+
 #[derive(Clone)]
 pub struct GuildData {
     pub id: u64,
@@ -21,6 +33,11 @@ pub struct GuildData {
     pub volume: u8,
     pub allow_greets: AllowGreet,
     pub allowed_role: Option<u64>,
+    pub playback_policy: PlaybackPolicy,
+    pub fade_in_ms: u32,
+    pub fade_out_ms: u32,
+    pub idle_timeout: u32,
+    pub max_duration: Option<u32>,
 }
 
 #[async_trait]
@@ -78,7 +95,8 @@ impl GuildData {
 
         let guild_data = sqlx::query_as_unchecked!(
             GuildData,
-            "SELECT id, prefix, volume, allow_greets, allowed_role
+            "SELECT id, prefix, volume, allow_greets, allowed_role, playback_policy, fade_in_ms,
+                fade_out_ms, idle_timeout, max_duration
                 FROM servers
                 WHERE id = ?",
             guild_id.get()
@@ -115,6 +133,11 @@ impl GuildData {
             volume: 100,
             allow_greets: AllowGreet::Enabled,
             allowed_role: None,
+            playback_policy: PlaybackPolicy::Overlay,
+            fade_in_ms: 0,
+            fade_out_ms: 0,
+            idle_timeout: 0,
+            max_duration: None,
         })
     }
 
@@ -129,7 +152,12 @@ SET
     prefix = ?,
     volume = ?,
     allow_greets = ?,
-    allowed_role = ?
+    allowed_role = ?,
+    playback_policy = ?,
+    fade_in_ms = ?,
+    fade_out_ms = ?,
+    idle_timeout = ?,
+    max_duration = ?
 WHERE
     id = ?
             ",
@@ -137,6 +165,11 @@ WHERE
             self.volume,
             self.allow_greets,
             self.allowed_role,
+            self.playback_policy,
+            self.fade_in_ms,
+            self.fade_out_ms,
+            self.idle_timeout,
+            self.max_duration,
             self.id
         )
         .execute(db_pool)
@@ -1,9 +1,27 @@
+use std::{collections::HashMap, io::Cursor, time::Duration};
+
+use log::{info, warn};
 use poise::serenity_prelude::async_trait;
-use songbird::input::Input;
+use serde::{Deserialize, Serialize};
+use songbird::{
+    events::{Event, EventData, TrackEvent},
+    input::Input,
+    tracks::Track,
+};
 use sqlx::Executor;
+use symphonia::core::{
+    formats::FormatOptions, io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+};
 use tokio::process::Command;
 
-use crate::{consts::UPLOAD_MAX_SIZE, error::ErrorTypes, Data, Database};
+use crate::{
+    consts::{LIST_PAGE_SIZE, MAX_LOUDNESS_GAIN, MAX_TAG_LENGTH, TARGET_LOUDNESS, UPLOAD_MAX_SIZE},
+    effects::{self, Effects},
+    error::ErrorTypes,
+    ffmpeg, fuzzy,
+    playback::{PlayTrigger, TrimEnd, TrimStart, TRIM_CHECK_PERIOD},
+    Data, Database,
+};
 
 #[derive(Clone)]
 pub struct Sound {
@@ -12,6 +30,9 @@ pub struct Sound {
     pub public: bool,
     pub server_id: u64,
     pub uploader_id: Option<u64>,
+    pub trim_start_ms: u32,
+    pub trim_end_ms: Option<u32>,
+    pub loudness: Option<f32>,
 }
 
 impl PartialEq for Sound {
@@ -29,12 +50,25 @@ pub trait SoundCtx {
         user_id: U,
         strict: bool,
     ) -> Result<Vec<Sound>, sqlx::Error>;
+    async fn search_sounds<G: Into<u64> + Send, U: Into<u64> + Send>(
+        &self,
+        query: &str,
+        guild_id: G,
+        user_id: U,
+        filters: &SearchFilters,
+    ) -> Result<Vec<Sound>, sqlx::Error>;
     async fn autocomplete_user_sounds<U: Into<u64> + Send, G: Into<u64> + Send>(
         &self,
         query: &str,
         user_id: U,
         guild_id: G,
     ) -> Result<Vec<Sound>, sqlx::Error>;
+    async fn autocomplete_managed_sounds<U: Into<u64> + Send, G: Into<u64> + Send>(
+        &self,
+        query: &str,
+        user_id: U,
+        guild_id: Option<G>,
+    ) -> Result<Vec<Sound>, sqlx::Error>;
     async fn autocomplete_favorite_sounds<U: Into<u64> + Send>(
         &self,
         query: &str,
@@ -44,25 +78,37 @@ pub trait SoundCtx {
         &self,
         user_id: U,
         page: Option<u64>,
+        tag: Option<&str>,
+        sort: SoundSort,
     ) -> Result<Vec<Sound>, sqlx::Error>;
     async fn favorite_sounds<U: Into<u64> + Send>(
         &self,
         user_id: U,
         page: Option<u64>,
+        tag: Option<&str>,
+        sort: SoundSort,
     ) -> Result<Vec<Sound>, sqlx::Error>;
     async fn guild_sounds<G: Into<u64> + Send>(
         &self,
         guild_id: G,
         page: Option<u64>,
+        tag: Option<&str>,
+        sort: SoundSort,
     ) -> Result<Vec<Sound>, sqlx::Error>;
-    async fn count_user_sounds<U: Into<u64> + Send>(&self, user_id: U) -> Result<u64, sqlx::Error>;
+    async fn count_user_sounds<U: Into<u64> + Send>(
+        &self,
+        user_id: U,
+        tag: Option<&str>,
+    ) -> Result<u64, sqlx::Error>;
     async fn count_favorite_sounds<U: Into<u64> + Send>(
         &self,
         user_id: U,
+        tag: Option<&str>,
     ) -> Result<u64, sqlx::Error>;
     async fn count_guild_sounds<G: Into<u64> + Send>(
         &self,
         guild_id: G,
+        tag: Option<&str>,
     ) -> Result<u64, sqlx::Error>;
 }
 
@@ -97,9 +143,9 @@ impl SoundCtx for Data {
             let sound = sqlx::query_as_unchecked!(
                 Sound,
                 "
-                SELECT name, id, public, server_id, uploader_id
+                SELECT name, id, public, server_id, uploader_id, trim_start_ms, trim_end_ms, loudness
                     FROM sounds
-                    WHERE id = ? AND (
+                    WHERE deleted_at IS NULL AND id = ? AND (
                         public = 1 OR
                         uploader_id = ? OR
                         server_id = ?
@@ -113,20 +159,34 @@ impl SoundCtx for Data {
 
             Ok(sound)
         } else {
-            let name = query;
+            let (name, tags) = parse_query(query);
+            let tag_list = tags.join(",");
             let sound;
 
+            if strict && name.is_empty() && tags.is_empty() {
+                return Ok(vec![]);
+            }
+
             if strict {
                 sound = sqlx::query_as_unchecked!(
                     Sound,
                     "
-                    SELECT name, id, public, server_id, uploader_id
+                    SELECT name, id, public, server_id, uploader_id, trim_start_ms, trim_end_ms, loudness
                         FROM sounds
-                        WHERE name = ? AND (
+                        WHERE deleted_at IS NULL AND (name = ? OR ? = '' OR EXISTS(
+                            SELECT 1
+                            FROM sound_aliases
+                            WHERE sound_aliases.sound_id = sounds.id AND sound_aliases.name = ?
+                        )) AND (
                             public = 1 OR
                             uploader_id = ? OR
                             server_id = ?
-                        )
+                        ) AND (? = 0 OR (
+                            SELECT COUNT(1)
+                            FROM sound_tags
+                            INNER JOIN tags ON tags.id = sound_tags.tag_id
+                            WHERE sound_tags.sound_id = sounds.id AND FIND_IN_SET(tags.name, ?)
+                        ) = ?)
                         ORDER BY
                             uploader_id = ? DESC,
                             EXISTS(
@@ -138,50 +198,146 @@ impl SoundCtx for Data {
                             public = 1 DESC,
                             rand()",
                     name,
-                    user_id,
-                    guild_id,
-                    user_id,
-                    user_id,
-                    guild_id
-                )
-                .fetch_all(&db_pool)
-                .await?;
-            } else {
-                sound = sqlx::query_as_unchecked!(
-                    Sound,
-                    "
-                    SELECT name, id, public, server_id, uploader_id
-                        FROM sounds
-                        WHERE name LIKE CONCAT('%', ?, '%') AND (
-                            public = 1 OR
-                            uploader_id = ? OR
-                            server_id = ?
-                        )
-                        ORDER BY
-                            uploader_id = ? DESC,
-                            EXISTS(
-                                SELECT 1
-                                FROM favorite_sounds
-                                WHERE sound_id = id AND user_id = ?
-                            ) DESC,
-                            server_id = ? DESC,
-                            public = 1 DESC,
-                            rand()",
+                    name,
                     name,
                     user_id,
                     guild_id,
+                    tags.len() as u32,
+                    tag_list,
+                    tags.len() as u32,
                     user_id,
                     user_id,
                     guild_id
                 )
                 .fetch_all(&db_pool)
                 .await?;
+            } else {
+                sound = self
+                    .search_sounds(query, guild_id, user_id, &SearchFilters::default())
+                    .await?;
             }
 
             Ok(sound)
         }
     }
 
+    async fn search_sounds<G: Into<u64> + Send, U: Into<u64> + Send>(
+        &self,
+        query: &str,
+        guild_id: G,
+        user_id: U,
+        filters: &SearchFilters,
+    ) -> Result<Vec<Sound>, sqlx::Error> {
+        let guild_id = guild_id.into();
+        let user_id = user_id.into();
+
+        let (name, tags) = parse_query(query);
+        let tag_list = tags.join(",");
+
+        let pattern = fuzzy::bigram_pattern(&name);
+
+        // Candidates are narrowed down to those sharing part of the name or an alias before
+        // they are scored, so the limit doesn't cut off matches in large libraries
+        let candidates = sqlx::query_as_unchecked!(
+            Sound,
+            "
+            SELECT name, id, public, server_id, uploader_id, trim_start_ms, trim_end_ms, loudness
+                FROM sounds
+                WHERE deleted_at IS NULL AND (
+                    uploader_id = ? OR
+                    server_id = ? OR
+                    public = 1
+                ) AND (? = '' OR name REGEXP ? OR EXISTS(
+                    SELECT 1
+                    FROM sound_aliases
+                    WHERE sound_aliases.sound_id = sounds.id AND sound_aliases.name REGEXP ?
+                )) AND
+                (? IS NULL OR uploader_id = ?) AND
+                (? IS NULL OR server_id = ?) AND
+                (? IS NULL OR public = ?) AND
+                (? IS NULL OR duration_ms >= ?) AND
+                (? IS NULL OR duration_ms <= ?) AND
+                (? = 0 OR EXISTS(
+                    SELECT 1
+                    FROM favorite_sounds
+                    WHERE sound_id = id AND user_id = ?
+                )) AND (? = 0 OR (
+                    SELECT COUNT(1)
+                    FROM sound_tags
+                    INNER JOIN tags ON tags.id = sound_tags.tag_id
+                    WHERE sound_tags.sound_id = sounds.id AND FIND_IN_SET(tags.name, ?)
+                ) = ?)
+                ORDER BY
+                    uploader_id = ? DESC,
+                    EXISTS(
+                        SELECT 1
+                        FROM favorite_sounds
+                        WHERE sound_id = id AND user_id = ?
+                    ) DESC,
+                    server_id = ? DESC,
+                    public = 1 DESC,
+                    plays DESC
+                LIMIT 1000",
+            user_id,
+            guild_id,
+            name,
+            pattern,
+            pattern,
+            filters.uploader_id,
+            filters.uploader_id,
+            filters.server_id,
+            filters.server_id,
+            filters.public,
+            filters.public,
+            filters.min_duration_ms,
+            filters.min_duration_ms,
+            filters.max_duration_ms,
+            filters.max_duration_ms,
+            filters.favorites_only,
+            user_id,
+            tags.len() as u32,
+            tag_list,
+            tags.len() as u32,
+            user_id,
+            user_id,
+            guild_id
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        let mut aliases = if name.is_empty() {
+            HashMap::new()
+        } else {
+            Sound::aliases_of(&candidates, &self.database).await?
+        };
+
+        let mut scored = candidates
+            .into_iter()
+            .map(|sound| {
+                // A sound scores as well as the best of its name and aliases
+                let score = if name.is_empty() {
+                    1.0
+                } else {
+                    aliases
+                        .remove(&sound.id)
+                        .unwrap_or_default()
+                        .iter()
+                        .map(|alias| fuzzy::similarity(&name, alias))
+                        .fold(fuzzy::similarity(&name, &sound.name), f32::max)
+                };
+
+                (sound, score)
+            })
+            .filter(|(_, score)| *score >= fuzzy::MATCH_THRESHOLD)
+            .collect::<Vec<_>>();
+
+        // Candidates are already ranked by ownership, favorites and popularity, which the stable
+        // sort keeps between similarly good matches
+        scored.sort_by_key(|(_, score)| std::cmp::Reverse((score * 10.0) as u32));
+
+        Ok(scored.into_iter().map(|(sound, _)| sound).collect())
+    }
+
     async fn autocomplete_user_sounds<U: Into<u64> + Send, G: Into<u64> + Send>(
         &self,
         query: &str,
@@ -189,22 +345,80 @@ impl SoundCtx for Data {
         guild_id: G,
     ) -> Result<Vec<Sound>, sqlx::Error> {
         let db_pool = self.database.clone();
+        let query = fuzzy::escape_like(query);
         let user_id = user_id.into();
+        let guild_id = guild_id.into();
 
+        // Public sounds are only suggested once something has been typed, to keep an empty
+        // prompt to the caller's own sounds. The caller's plays are grouped once and joined,
+        // rather than looked up for every candidate
         sqlx::query_as_unchecked!(
             Sound,
             "
-            SELECT name, id, public, server_id, uploader_id
+            SELECT name, id, public, server_id, uploader_id, trim_start_ms, trim_end_ms, loudness
             FROM sounds
-            WHERE name LIKE CONCAT(?, '%') AND (uploader_id = ? OR server_id = ? OR EXISTS(
+            LEFT JOIN (
+                SELECT sound_id, MAX(played_at) AS last_played
+                FROM sound_plays
+                WHERE user_id = ?
+                GROUP BY sound_id
+            ) recent ON recent.sound_id = sounds.id
+            WHERE deleted_at IS NULL AND (name LIKE CONCAT(?, '%') OR EXISTS(
+                SELECT 1
+                FROM sound_aliases
+                WHERE sound_aliases.sound_id = sounds.id AND sound_aliases.name LIKE CONCAT(?, '%')
+            )) AND (uploader_id = ? OR server_id = ? OR EXISTS(
                 SELECT 1
                 FROM favorite_sounds
-                WHERE sound_id = id AND user_id = ?
-            ))
+                WHERE favorite_sounds.sound_id = sounds.id AND favorite_sounds.user_id = ?
+            ) OR (public = 1 AND ? != ''))
+            ORDER BY
+                recent.last_played DESC,
+                uploader_id = ? DESC,
+                server_id = ? DESC,
+                plays DESC
             LIMIT 25",
+            user_id,
+            query,
             query,
             user_id,
-            guild_id.into(),
+            guild_id,
+            user_id,
+            query,
+            user_id,
+            guild_id,
+        )
+        .fetch_all(&db_pool)
+        .await
+    }
+
+    async fn autocomplete_managed_sounds<U: Into<u64> + Send, G: Into<u64> + Send>(
+        &self,
+        query: &str,
+        user_id: U,
+        guild_id: Option<G>,
+    ) -> Result<Vec<Sound>, sqlx::Error> {
+        let db_pool = self.database.clone();
+        let query = fuzzy::escape_like(query);
+        let user_id = user_id.into();
+
+        // Without a guild, `server_id = NULL` matches nothing and only uploads are suggested
+        sqlx::query_as_unchecked!(
+            Sound,
+            "
+            SELECT name, id, public, server_id, uploader_id, trim_start_ms, trim_end_ms, loudness
+            FROM sounds
+            WHERE deleted_at IS NULL AND (name LIKE CONCAT(?, '%') OR EXISTS(
+                SELECT 1
+                FROM sound_aliases
+                WHERE sound_aliases.sound_id = sounds.id AND sound_aliases.name LIKE CONCAT(?, '%')
+            )) AND (uploader_id = ? OR server_id = ?)
+            ORDER BY uploader_id = ? DESC, name
+            LIMIT 25",
+            query,
+            query,
+            user_id,
+            guild_id.map(|g| g.into()),
             user_id,
         )
         .fetch_all(&db_pool)
@@ -217,13 +431,14 @@ impl SoundCtx for Data {
         user_id: U,
     ) -> Result<Vec<Sound>, sqlx::Error> {
         let db_pool = self.database.clone();
+        let query = fuzzy::escape_like(query);
 
         sqlx::query_as_unchecked!(
             Sound,
             "
-            SELECT name, id, public, server_id, uploader_id
+            SELECT name, id, public, server_id, uploader_id, trim_start_ms, trim_end_ms, loudness
             FROM sounds
-            WHERE name LIKE CONCAT(?, '%') AND EXISTS(
+            WHERE deleted_at IS NULL AND name LIKE CONCAT(?, '%') AND EXISTS(
                 SELECT 1
                 FROM favorite_sounds
                 WHERE sound_id = id AND user_id = ?
@@ -240,20 +455,36 @@ impl SoundCtx for Data {
         &self,
         user_id: U,
         page: Option<u64>,
+        tag: Option<&str>,
+        sort: SoundSort,
     ) -> Result<Vec<Sound>, sqlx::Error> {
         let sounds = match page {
             Some(page) => {
                 sqlx::query_as_unchecked!(
                     Sound,
                     "
-                    SELECT name, id, public, server_id, uploader_id
+                    SELECT name, id, public, server_id, uploader_id, trim_start_ms, trim_end_ms, loudness
                         FROM sounds
-                        WHERE uploader_id = ?
-                        ORDER BY id DESC
+                        WHERE deleted_at IS NULL AND uploader_id = ? AND (? IS NULL OR EXISTS(
+                            SELECT 1
+                            FROM sound_tags
+                            INNER JOIN tags ON tags.id = sound_tags.tag_id
+                            WHERE sound_tags.sound_id = sounds.id AND tags.name = ?
+                        ))
+                        ORDER BY
+                            CASE WHEN ? = 1 THEN name END ASC,
+                            CASE WHEN ? = 2 THEN id END ASC,
+                            CASE WHEN ? = 3 THEN plays END DESC,
+                            id DESC
                         LIMIT ?, ?",
                     user_id.into(),
-                    page * 25,
-                    (page + 1) * 25
+                    tag,
+                    tag,
+                    sort as u8,
+                    sort as u8,
+                    sort as u8,
+                    page * LIST_PAGE_SIZE,
+                    LIST_PAGE_SIZE
                 )
                 .fetch_all(&self.database)
                 .await?
@@ -262,11 +493,25 @@ impl SoundCtx for Data {
                 sqlx::query_as_unchecked!(
                     Sound,
                     "
-                    SELECT name, id, public, server_id, uploader_id
+                    SELECT name, id, public, server_id, uploader_id, trim_start_ms, trim_end_ms, loudness
                         FROM sounds
-                        WHERE uploader_id = ?
-                        ORDER BY id DESC",
-                    user_id.into()
+                        WHERE deleted_at IS NULL AND uploader_id = ? AND (? IS NULL OR EXISTS(
+                            SELECT 1
+                            FROM sound_tags
+                            INNER JOIN tags ON tags.id = sound_tags.tag_id
+                            WHERE sound_tags.sound_id = sounds.id AND tags.name = ?
+                        ))
+                        ORDER BY
+                            CASE WHEN ? = 1 THEN name END ASC,
+                            CASE WHEN ? = 2 THEN id END ASC,
+                            CASE WHEN ? = 3 THEN plays END DESC,
+                            id DESC",
+                    user_id.into(),
+                    tag,
+                    tag,
+                    sort as u8,
+                    sort as u8,
+                    sort as u8
                 )
                 .fetch_all(&self.database)
                 .await?
@@ -280,21 +525,37 @@ impl SoundCtx for Data {
         &self,
         user_id: U,
         page: Option<u64>,
+        tag: Option<&str>,
+        sort: SoundSort,
     ) -> Result<Vec<Sound>, sqlx::Error> {
         let sounds = match page {
             Some(page) => {
                 sqlx::query_as_unchecked!(
                     Sound,
                     "
-                    SELECT name, id, public, server_id, uploader_id
+                    SELECT name, id, public, server_id, uploader_id, trim_start_ms, trim_end_ms, loudness
                         FROM sounds
                         INNER JOIN favorite_sounds f ON sounds.id = f.sound_id
-                        WHERE f.user_id = ?
-                        ORDER BY id DESC
+                        WHERE deleted_at IS NULL AND f.user_id = ? AND (? IS NULL OR EXISTS(
+                            SELECT 1
+                            FROM sound_tags
+                            INNER JOIN tags ON tags.id = sound_tags.tag_id
+                            WHERE sound_tags.sound_id = sounds.id AND tags.name = ?
+                        ))
+                        ORDER BY
+                            CASE WHEN ? = 1 THEN name END ASC,
+                            CASE WHEN ? = 2 THEN id END ASC,
+                            CASE WHEN ? = 3 THEN plays END DESC,
+                            id DESC
                         LIMIT ?, ?",
                     user_id.into(),
-                    page * 25,
-                    (page + 1) * 25
+                    tag,
+                    tag,
+                    sort as u8,
+                    sort as u8,
+                    sort as u8,
+                    page * LIST_PAGE_SIZE,
+                    LIST_PAGE_SIZE
                 )
                 .fetch_all(&self.database)
                 .await?
@@ -303,12 +564,26 @@ impl SoundCtx for Data {
                 sqlx::query_as_unchecked!(
                     Sound,
                     "
-                    SELECT name, id, public, server_id, uploader_id
+                    SELECT name, id, public, server_id, uploader_id, trim_start_ms, trim_end_ms, loudness
                         FROM sounds
                         INNER JOIN favorite_sounds f ON sounds.id = f.sound_id
-                        WHERE f.user_id = ?
-                        ORDER BY id DESC",
-                    user_id.into()
+                        WHERE deleted_at IS NULL AND f.user_id = ? AND (? IS NULL OR EXISTS(
+                            SELECT 1
+                            FROM sound_tags
+                            INNER JOIN tags ON tags.id = sound_tags.tag_id
+                            WHERE sound_tags.sound_id = sounds.id AND tags.name = ?
+                        ))
+                        ORDER BY
+                            CASE WHEN ? = 1 THEN name END ASC,
+                            CASE WHEN ? = 2 THEN id END ASC,
+                            CASE WHEN ? = 3 THEN plays END DESC,
+                            id DESC",
+                    user_id.into(),
+                    tag,
+                    tag,
+                    sort as u8,
+                    sort as u8,
+                    sort as u8
                 )
                 .fetch_all(&self.database)
                 .await?
@@ -322,20 +597,36 @@ impl SoundCtx for Data {
         &self,
         guild_id: G,
         page: Option<u64>,
+        tag: Option<&str>,
+        sort: SoundSort,
     ) -> Result<Vec<Sound>, sqlx::Error> {
         let sounds = match page {
             Some(page) => {
                 sqlx::query_as_unchecked!(
                     Sound,
                     "
-                    SELECT name, id, public, server_id, uploader_id
+                    SELECT name, id, public, server_id, uploader_id, trim_start_ms, trim_end_ms, loudness
                         FROM sounds
-                        WHERE server_id = ?
-                        ORDER BY id DESC
+                        WHERE deleted_at IS NULL AND server_id = ? AND (? IS NULL OR EXISTS(
+                            SELECT 1
+                            FROM sound_tags
+                            INNER JOIN tags ON tags.id = sound_tags.tag_id
+                            WHERE sound_tags.sound_id = sounds.id AND tags.name = ?
+                        ))
+                        ORDER BY
+                            CASE WHEN ? = 1 THEN name END ASC,
+                            CASE WHEN ? = 2 THEN id END ASC,
+                            CASE WHEN ? = 3 THEN plays END DESC,
+                            id DESC
                         LIMIT ?, ?",
                     guild_id.into(),
-                    page * 25,
-                    (page + 1) * 25
+                    tag,
+                    tag,
+                    sort as u8,
+                    sort as u8,
+                    sort as u8,
+                    page * LIST_PAGE_SIZE,
+                    LIST_PAGE_SIZE
                 )
                 .fetch_all(&self.database)
                 .await?
@@ -345,11 +636,25 @@ impl SoundCtx for Data {
                 sqlx::query_as_unchecked!(
                     Sound,
                     "
-                    SELECT name, id, public, server_id, uploader_id
+                    SELECT name, id, public, server_id, uploader_id, trim_start_ms, trim_end_ms, loudness
                         FROM sounds
-                        WHERE server_id = ?
-                        ORDER BY id DESC",
-                    guild_id.into()
+                        WHERE deleted_at IS NULL AND server_id = ? AND (? IS NULL OR EXISTS(
+                            SELECT 1
+                            FROM sound_tags
+                            INNER JOIN tags ON tags.id = sound_tags.tag_id
+                            WHERE sound_tags.sound_id = sounds.id AND tags.name = ?
+                        ))
+                        ORDER BY
+                            CASE WHEN ? = 1 THEN name END ASC,
+                            CASE WHEN ? = 2 THEN id END ASC,
+                            CASE WHEN ? = 3 THEN plays END DESC,
+                            id DESC",
+                    guild_id.into(),
+                    tag,
+                    tag,
+                    sort as u8,
+                    sort as u8,
+                    sort as u8
                 )
                 .fetch_all(&self.database)
                 .await?
@@ -359,10 +664,24 @@ impl SoundCtx for Data {
         Ok(sounds)
     }
 
-    async fn count_user_sounds<U: Into<u64> + Send>(&self, user_id: U) -> Result<u64, sqlx::Error> {
+    async fn count_user_sounds<U: Into<u64> + Send>(
+        &self,
+        user_id: U,
+        tag: Option<&str>,
+    ) -> Result<u64, sqlx::Error> {
         Ok(sqlx::query!(
-            "SELECT COUNT(1) as count FROM sounds WHERE uploader_id = ?",
-            user_id.into()
+            "
+            SELECT COUNT(1) as count
+                FROM sounds
+                WHERE deleted_at IS NULL AND uploader_id = ? AND (? IS NULL OR EXISTS(
+                    SELECT 1
+                    FROM sound_tags
+                    INNER JOIN tags ON tags.id = sound_tags.tag_id
+                    WHERE sound_tags.sound_id = sounds.id AND tags.name = ?
+                ))",
+            user_id.into(),
+            tag,
+            tag
         )
         .fetch_one(&self.database)
         .await?
@@ -372,10 +691,22 @@ impl SoundCtx for Data {
     async fn count_favorite_sounds<U: Into<u64> + Send>(
         &self,
         user_id: U,
+        tag: Option<&str>,
     ) -> Result<u64, sqlx::Error> {
         Ok(sqlx::query!(
-            "SELECT COUNT(1) as count FROM favorite_sounds WHERE user_id = ?",
-            user_id.into()
+            "
+            SELECT COUNT(1) as count
+                FROM sounds
+                INNER JOIN favorite_sounds f ON sounds.id = f.sound_id
+                WHERE deleted_at IS NULL AND f.user_id = ? AND (? IS NULL OR EXISTS(
+                    SELECT 1
+                    FROM sound_tags
+                    INNER JOIN tags ON tags.id = sound_tags.tag_id
+                    WHERE sound_tags.sound_id = sounds.id AND tags.name = ?
+                ))",
+            user_id.into(),
+            tag,
+            tag
         )
         .fetch_one(&self.database)
         .await?
@@ -385,10 +716,21 @@ impl SoundCtx for Data {
     async fn count_guild_sounds<G: Into<u64> + Send>(
         &self,
         guild_id: G,
+        tag: Option<&str>,
     ) -> Result<u64, sqlx::Error> {
         Ok(sqlx::query!(
-            "SELECT COUNT(1) as count FROM sounds WHERE server_id = ?",
-            guild_id.into()
+            "
+            SELECT COUNT(1) as count
+                FROM sounds
+                WHERE deleted_at IS NULL AND server_id = ? AND (? IS NULL OR EXISTS(
+                    SELECT 1
+                    FROM sound_tags
+                    INNER JOIN tags ON tags.id = sound_tags.tag_id
+                    WHERE sound_tags.sound_id = sounds.id AND tags.name = ?
+                ))",
+            guild_id.into(),
+            tag,
+            tag
         )
         .fetch_one(&self.database)
         .await?
@@ -397,6 +739,53 @@ impl SoundCtx for Data {
 }
 
 impl Sound {
+    pub async fn from_id(
+        id: u32,
+        db_pool: impl Executor<'_, Database = Database>,
+    ) -> Result<Option<Sound>, sqlx::Error> {
+        sqlx::query_as_unchecked!(
+            Sound,
+            "
+            SELECT name, id, public, server_id, uploader_id, trim_start_ms, trim_end_ms, loudness
+                FROM sounds
+                WHERE deleted_at IS NULL AND id = ?",
+            id
+        )
+        .fetch_optional(db_pool)
+        .await
+    }
+
+    /// Sounds by ID, in the order of `ids`. Sounds that no longer exist are left out
+    pub async fn from_ids(
+        ids: &[u32],
+        db_pool: impl Executor<'_, Database = Database>,
+    ) -> Result<Vec<Sound>, sqlx::Error> {
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let id_list = ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let mut sounds = sqlx::query_as_unchecked!(
+            Sound,
+            "
+            SELECT name, id, public, server_id, uploader_id, trim_start_ms, trim_end_ms, loudness
+                FROM sounds
+                WHERE deleted_at IS NULL AND FIND_IN_SET(id, ?)",
+            id_list
+        )
+        .fetch_all(db_pool)
+        .await?;
+
+        sounds.sort_by_key(|sound| ids.iter().position(|id| *id == sound.id));
+
+        Ok(sounds)
+    }
+
     pub(crate) async fn src(&self, db_pool: impl Executor<'_, Database = Database>) -> Vec<u8> {
         struct Src {
             src: Vec<u8>,
@@ -421,8 +810,181 @@ impl Sound {
     pub async fn playable(
         &self,
         db_pool: impl Executor<'_, Database = Database>,
-    ) -> Result<Input, Box<dyn std::error::Error + Send + Sync>> {
-        Ok(Input::from(self.src(db_pool).await))
+        effects: &Effects,
+    ) -> Result<Track, Box<dyn std::error::Error + Send + Sync>> {
+        let src = self.src(db_pool).await;
+
+        // Rendered sounds have their trim points applied already
+        if !effects.is_empty() {
+            return match effects
+                .render(self.id, &src, self.trim_start_ms, self.trim_end_ms)
+                .await
+            {
+                Some(rendered) => Ok(Track::from(Input::from(rendered))),
+                None => Err(Box::new(ErrorTypes::RenderFailed)),
+            };
+        }
+
+        let mut track = Track::from(Input::from(src));
+
+        if self.trim_start_ms > 0 {
+            let start = Duration::from_millis(self.trim_start_ms as u64);
+
+            for event in [TrackEvent::Playable, TrackEvent::Loop] {
+                track.events.add_event(
+                    EventData::new(Event::Track(event), TrimStart { start }),
+                    Duration::ZERO,
+                );
+            }
+        }
+
+        if let Some(end_ms) = self.trim_end_ms {
+            track.events.add_event(
+                EventData::new(
+                    Event::Periodic(TRIM_CHECK_PERIOD, None),
+                    TrimEnd {
+                        start: Duration::from_millis(self.trim_start_ms as u64),
+                        end: Duration::from_millis(end_ms as u64),
+                    },
+                ),
+                Duration::ZERO,
+            );
+        }
+
+        Ok(track)
+    }
+
+    /// Length of the sound's audio, as recorded when it was uploaded
+    pub async fn duration(
+        &self,
+        db_pool: impl Executor<'_, Database = Database>,
+    ) -> Option<Duration> {
+        sqlx::query!("SELECT duration_ms FROM sounds WHERE id = ?", self.id)
+            .fetch_one(db_pool)
+            .await
+            .ok()?
+            .duration_ms
+            .map(|ms| Duration::from_millis(ms as u64))
+    }
+
+    /// Length of the part of a sound of `duration` between its trim points
+    pub fn trimmed_duration(&self, duration: Duration) -> Duration {
+        let start = Duration::from_millis(self.trim_start_ms as u64);
+        let end = self.trim_end_ms.map_or(duration, |end| {
+            duration.min(Duration::from_millis(end as u64))
+        });
+
+        end.saturating_sub(start)
+    }
+
+    /// Details of the sound shown by `/sound info`
+    pub async fn details(
+        &self,
+        db_pool: impl Executor<'_, Database = Database>,
+    ) -> Result<SoundDetails, sqlx::Error> {
+        sqlx::query_as_unchecked!(
+            SoundDetails,
+            "
+            SELECT
+                CAST(UNIX_TIMESTAMP(created_at) AS SIGNED) AS created_at,
+                duration_ms,
+                size_bytes,
+                CAST(plays AS SIGNED) AS plays,
+                (
+                    SELECT COUNT(1)
+                    FROM favorite_sounds
+                    WHERE sound_id = sounds.id
+                ) AS favorites,
+                cloned_from
+                FROM sounds
+                WHERE id = ?",
+            self.id
+        )
+        .fetch_one(db_pool)
+        .await
+    }
+
+    /// Volume multiplier that brings the sound to the target loudness
+    pub fn gain(&self) -> f32 {
+        match self.loudness {
+            Some(loudness) => 10f32
+                .powf((TARGET_LOUDNESS - loudness) / 20.0)
+                .min(MAX_LOUDNESS_GAIN),
+
+            None => 1.0,
+        }
+    }
+
+    /// Measure the loudness of sounds uploaded before loudness was recorded
+    pub async fn backfill_loudness(
+        db_pool: impl Executor<'_, Database = Database> + Copy,
+    ) -> Result<(), sqlx::Error> {
+        struct Id {
+            id: u32,
+        }
+
+        let ids = sqlx::query_as!(Id, "SELECT id FROM sounds WHERE loudness IS NULL")
+            .fetch_all(db_pool)
+            .await?;
+
+        info!("Measuring loudness of {} sounds", ids.len());
+
+        for Id { id } in ids {
+            let src = sqlx::query!("SELECT src FROM sounds WHERE id = ?", id)
+                .fetch_one(db_pool)
+                .await?
+                .src;
+
+            match measure_loudness(&src).await {
+                Some(loudness) => {
+                    sqlx::query!("UPDATE sounds SET loudness = ? WHERE id = ?", loudness, id)
+                        .execute(db_pool)
+                        .await?;
+                }
+
+                None => warn!("Couldn't measure loudness of sound {}", id),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Record the duration of sounds uploaded before durations were recorded
+    pub async fn backfill_duration(
+        db_pool: impl Executor<'_, Database = Database> + Copy,
+    ) -> Result<(), sqlx::Error> {
+        struct Id {
+            id: u32,
+        }
+
+        let ids = sqlx::query_as!(Id, "SELECT id FROM sounds WHERE duration_ms IS NULL")
+            .fetch_all(db_pool)
+            .await?;
+
+        info!("Probing duration of {} sounds", ids.len());
+
+        for Id { id } in ids {
+            let src = sqlx::query!("SELECT src FROM sounds WHERE id = ?", id)
+                .fetch_one(db_pool)
+                .await?
+                .src;
+
+            match probe_duration(src) {
+                Some(duration) => {
+                    sqlx::query!(
+                        "UPDATE sounds SET duration_ms = ? WHERE id = ?",
+                        duration.as_millis() as u32,
+                        id
+                    )
+                    .execute(db_pool)
+                    .await?;
+                }
+
+                None => warn!("Couldn't probe duration of sound {}", id),
+            }
+        }
+
+        Ok(())
     }
 
     pub async fn count_user_sounds<U: Into<u64>>(
@@ -435,7 +997,7 @@ impl Sound {
             "
             SELECT COUNT(1) as count
                 FROM sounds
-                WHERE uploader_id = ?",
+                WHERE deleted_at IS NULL AND uploader_id = ?",
             user_id
         )
         .fetch_one(db_pool)
@@ -445,6 +1007,7 @@ impl Sound {
         Ok(c as u32)
     }
 
+    /// Count the user's sounds that have a name or alias
     pub async fn count_named_user_sounds<U: Into<u64>>(
         user_id: U,
         name: &String,
@@ -457,9 +1020,17 @@ impl Sound {
             SELECT COUNT(1) as count
                 FROM sounds
                 WHERE
-                    uploader_id = ? AND
-                    name = ?",
+                    deleted_at IS NULL AND
+                    uploader_id = ? AND (
+                        name = ? OR
+                        EXISTS(
+                            SELECT 1
+                            FROM sound_aliases
+                            WHERE sound_aliases.sound_id = sounds.id AND sound_aliases.name = ?
+                        )
+                    )",
             user_id,
+            name,
             name
         )
         .fetch_one(db_pool)
@@ -469,6 +1040,51 @@ impl Sound {
         Ok(c as u32)
     }
 
+    /// The first of this sound's name and aliases that one of a user's other sounds already uses,
+    /// checked before the sound is given to or restored for that user
+    pub async fn conflicting_name<U: Into<u64>>(
+        &self,
+        user_id: U,
+        db_pool: impl Executor<'_, Database = Database> + Copy,
+    ) -> Result<Option<String>, sqlx::Error> {
+        let user_id = user_id.into();
+
+        let mut names = vec![self.name.clone()];
+        names.extend(self.aliases(db_pool).await?);
+
+        for name in names {
+            let count = sqlx::query!(
+                "
+                SELECT COUNT(1) as count
+                    FROM sounds
+                    WHERE
+                        deleted_at IS NULL AND
+                        uploader_id = ? AND
+                        id != ? AND (
+                            name = ? OR
+                            EXISTS(
+                                SELECT 1
+                                FROM sound_aliases
+                                WHERE sound_aliases.sound_id = sounds.id AND sound_aliases.name = ?
+                            )
+                        )",
+                user_id,
+                self.id,
+                name,
+                name
+            )
+            .fetch_one(db_pool)
+            .await?
+            .count;
+
+            if count > 0 {
+                return Ok(Some(name));
+            }
+        }
+
+        Ok(None)
+    }
+
     pub async fn commit(
         &self,
         db_pool: impl Executor<'_, Database = Database>,
@@ -477,10 +1093,18 @@ impl Sound {
             "
             UPDATE sounds
             SET
-                public = ?
+                name = ?,
+                uploader_id = ?,
+                public = ?,
+                trim_start_ms = ?,
+                trim_end_ms = ?
             WHERE
                 id = ?",
+            self.name,
+            self.uploader_id,
             self.public,
+            self.trim_start_ms,
+            self.trim_end_ms,
             self.id
         )
         .execute(db_pool)
@@ -489,17 +1113,255 @@ impl Sound {
         Ok(())
     }
 
-    pub async fn delete(
+    /// Move the sound to the trash, where it is hidden until restored or purged
+    pub async fn trash(
         &self,
         db_pool: impl Executor<'_, Database = Database>,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        sqlx::query!("DELETE FROM sounds WHERE id = ?", self.id)
+        sqlx::query!("UPDATE sounds SET deleted_at = NOW() WHERE id = ?", self.id)
             .execute(db_pool)
             .await?;
 
         Ok(())
     }
 
+    pub async fn restore(
+        &self,
+        db_pool: impl Executor<'_, Database = Database>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        sqlx::query!("UPDATE sounds SET deleted_at = NULL WHERE id = ?", self.id)
+            .execute(db_pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Trashed sounds uploaded by a user or to a guild, most recently trashed first
+    pub async fn trashed<G: Into<u64>, U: Into<u64>>(
+        guild_id: G,
+        user_id: U,
+        db_pool: impl Executor<'_, Database = Database>,
+    ) -> Result<Vec<Sound>, sqlx::Error> {
+        sqlx::query_as_unchecked!(
+            Sound,
+            "
+            SELECT name, id, public, server_id, uploader_id, trim_start_ms, trim_end_ms, loudness
+                FROM sounds
+                WHERE deleted_at IS NOT NULL AND (uploader_id = ? OR server_id = ?)
+                ORDER BY deleted_at DESC",
+            user_id.into(),
+            guild_id.into()
+        )
+        .fetch_all(db_pool)
+        .await
+    }
+
+    /// Delete plays older than `days` from the play history. Play counts on sounds are kept, but
+    /// server leaderboards only count the plays still in the history
+    pub async fn purge_plays(
+        days: u32,
+        db_pool: impl Executor<'_, Database = Database>,
+    ) -> Result<u64, sqlx::Error> {
+        Ok(sqlx::query!(
+            "DELETE FROM sound_plays WHERE played_at < NOW() - INTERVAL ? DAY",
+            days
+        )
+        .execute(db_pool)
+        .await?
+        .rows_affected())
+    }
+
+    /// Permanently delete sounds that have been in the trash for longer than `days`
+    pub async fn purge_trash(
+        days: u32,
+        db_pool: impl Executor<'_, Database = Database>,
+    ) -> Result<u64, sqlx::Error> {
+        Ok(sqlx::query!(
+            "DELETE FROM sounds WHERE deleted_at < NOW() - INTERVAL ? DAY",
+            days
+        )
+        .execute(db_pool)
+        .await?
+        .rows_affected())
+    }
+
+    pub async fn aliases(
+        &self,
+        db_pool: impl Executor<'_, Database = Database>,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        Ok(sqlx::query!(
+            "SELECT name FROM sound_aliases WHERE sound_id = ? ORDER BY name",
+            self.id
+        )
+        .fetch_all(db_pool)
+        .await?
+        .into_iter()
+        .map(|row| row.name)
+        .collect())
+    }
+
+    /// Aliases of each of `sounds`, keyed by sound ID. Sounds without aliases are left out
+    pub async fn aliases_of(
+        sounds: &[Sound],
+        db_pool: impl Executor<'_, Database = Database>,
+    ) -> Result<HashMap<u32, Vec<String>>, sqlx::Error> {
+        if sounds.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let id_list = sounds
+            .iter()
+            .map(|sound| sound.id.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let rows = sqlx::query!(
+            "SELECT sound_id, name FROM sound_aliases WHERE FIND_IN_SET(sound_id, ?)",
+            id_list
+        )
+        .fetch_all(db_pool)
+        .await?;
+
+        let mut aliases = HashMap::<u32, Vec<String>>::new();
+        for row in rows {
+            aliases.entry(row.sound_id).or_default().push(row.name);
+        }
+
+        Ok(aliases)
+    }
+
+    pub async fn add_alias(
+        &self,
+        alias: &str,
+        db_pool: impl Executor<'_, Database = Database>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        sqlx::query!(
+            "INSERT INTO sound_aliases (sound_id, name) VALUES (?, ?)",
+            self.id,
+            alias
+        )
+        .execute(db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove an alias from the sound, returning whether it had the alias
+    pub async fn remove_alias(
+        &self,
+        alias: &str,
+        db_pool: impl Executor<'_, Database = Database>,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let result = sqlx::query!(
+            "DELETE FROM sound_aliases WHERE sound_id = ? AND name = ?",
+            self.id,
+            alias
+        )
+        .execute(db_pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Record a play of the sound
+    pub async fn log_play<G: Into<u64>, U: Into<u64>>(
+        &self,
+        guild_id: G,
+        channel_id: Option<u64>,
+        user_id: U,
+        trigger: PlayTrigger,
+        db_pool: impl Executor<'_, Database = Database> + Copy,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "
+            INSERT INTO sound_plays
+                (sound_id, sound_name, guild_id, channel_id, user_id, trigger_type)
+                VALUES (?, ?, ?, ?, ?, ?)",
+            self.id,
+            self.name,
+            guild_id.into(),
+            channel_id,
+            user_id.into(),
+            trigger.as_str()
+        )
+        .execute(db_pool)
+        .await?;
+
+        sqlx::query!("UPDATE sounds SET plays = plays + 1 WHERE id = ?", self.id)
+            .execute(db_pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// The sounds played most in a guild
+    pub async fn top_in_guild<G: Into<u64>>(
+        guild_id: G,
+        db_pool: impl Executor<'_, Database = Database>,
+    ) -> Result<Vec<TopSound>, sqlx::Error> {
+        sqlx::query_as_unchecked!(
+            TopSound,
+            "
+            SELECT sounds.id, sounds.name, COUNT(1) AS plays
+                FROM sound_plays
+                INNER JOIN sounds ON sounds.id = sound_plays.sound_id
+                WHERE sounds.deleted_at IS NULL AND sound_plays.guild_id = ?
+                GROUP BY sounds.id, sounds.name
+                ORDER BY plays DESC
+                LIMIT 10",
+            guild_id.into()
+        )
+        .fetch_all(db_pool)
+        .await
+    }
+
+    /// The latest plays in a guild, optionally only those by one user
+    pub async fn history<G: Into<u64>>(
+        guild_id: G,
+        user_id: Option<u64>,
+        db_pool: impl Executor<'_, Database = Database>,
+    ) -> Result<Vec<SoundPlay>, sqlx::Error> {
+        sqlx::query_as_unchecked!(
+            SoundPlay,
+            "
+            SELECT
+                sound_plays.sound_id,
+                sound_plays.sound_name AS name,
+                sounds.id IS NULL OR sounds.deleted_at IS NOT NULL AS trashed,
+                sound_plays.user_id,
+                sound_plays.channel_id,
+                sound_plays.trigger_type,
+                CAST(UNIX_TIMESTAMP(sound_plays.played_at) AS SIGNED) AS played_at
+                FROM sound_plays
+                LEFT JOIN sounds ON sounds.id = sound_plays.sound_id
+                WHERE sound_plays.guild_id = ? AND (? IS NULL OR sound_plays.user_id = ?)
+                ORDER BY sound_plays.id DESC
+                LIMIT 15",
+            guild_id.into(),
+            user_id,
+            user_id
+        )
+        .fetch_all(db_pool)
+        .await
+    }
+
+    /// The public sounds played most across all guilds
+    pub async fn top_public(
+        db_pool: impl Executor<'_, Database = Database>,
+    ) -> Result<Vec<TopSound>, sqlx::Error> {
+        sqlx::query_as_unchecked!(
+            TopSound,
+            "
+            SELECT id, name, CAST(plays AS SIGNED) AS plays
+                FROM sounds
+                WHERE deleted_at IS NULL AND public = 1 AND plays > 0
+                ORDER BY plays DESC
+                LIMIT 10"
+        )
+        .fetch_all(db_pool)
+        .await
+    }
+
     pub async fn add_favorite<U: Into<u64>>(
         &self,
         user_id: U,
@@ -536,67 +1398,382 @@ impl Sound {
         Ok(())
     }
 
+    pub async fn tags(
+        &self,
+        db_pool: impl Executor<'_, Database = Database>,
+    ) -> Result<Vec<String>, sqlx::Error> {
+        Ok(sqlx::query!(
+            "
+            SELECT tags.name
+                FROM tags
+                INNER JOIN sound_tags ON sound_tags.tag_id = tags.id
+                WHERE sound_tags.sound_id = ?
+                ORDER BY tags.name",
+            self.id
+        )
+        .fetch_all(db_pool)
+        .await?
+        .into_iter()
+        .map(|row| row.name)
+        .collect())
+    }
+
+    pub async fn add_tag(
+        &self,
+        tag: &str,
+        db_pool: impl Executor<'_, Database = Database> + Copy,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        sqlx::query!("INSERT IGNORE INTO tags (name) VALUES (?)", tag)
+            .execute(db_pool)
+            .await?;
+
+        sqlx::query!(
+            "
+            INSERT IGNORE INTO sound_tags (sound_id, tag_id)
+                SELECT ?, id FROM tags WHERE name = ?",
+            self.id,
+            tag
+        )
+        .execute(db_pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove a tag from the sound, returning whether it had the tag
+    pub async fn remove_tag(
+        &self,
+        tag: &str,
+        db_pool: impl Executor<'_, Database = Database>,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let result = sqlx::query!(
+            "
+            DELETE sound_tags
+                FROM sound_tags
+                INNER JOIN tags ON tags.id = sound_tags.tag_id
+                WHERE sound_tags.sound_id = ? AND tags.name = ?",
+            self.id,
+            tag
+        )
+        .execute(db_pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Swap the sound's audio for a new upload, keeping its ID. Trim points are reset, as they
+    /// refer to the old audio
+    pub async fn replace_src(
+        &mut self,
+        src_url: &str,
+        db_pool: impl Executor<'_, Database = Database>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let data = process_src(src_url).await.ok_or(ErrorTypes::InvalidFile)?;
+
+        let loudness = measure_loudness(&data).await;
+        let duration_ms = probe_duration(data.clone()).map(|d| d.as_millis() as u32);
+        let size_bytes = data.len() as u32;
+
+        sqlx::query!(
+            "
+            UPDATE sounds
+            SET
+                src = ?,
+                loudness = ?,
+                duration_ms = ?,
+                size_bytes = ?,
+                trim_start_ms = 0,
+                trim_end_ms = NULL
+            WHERE
+                id = ?",
+            data,
+            loudness,
+            duration_ms,
+            size_bytes,
+            self.id
+        )
+        .execute(db_pool)
+        .await?;
+
+        self.loudness = loudness;
+        self.trim_start_ms = 0;
+        self.trim_end_ms = None;
+
+        effects::clear_renders(self.id).await;
+
+        Ok(())
+    }
+
+    /// Copy the sound into a user's library, recording where it was copied from
+    pub async fn clone_to<G: Into<u64>, U: Into<u64>>(
+        &self,
+        name: &str,
+        server_id: G,
+        user_id: U,
+        db_pool: impl Executor<'_, Database = Database>,
+    ) -> Result<u32, Box<dyn std::error::Error + Send + Sync>> {
+        let result = sqlx::query!(
+            "
+            INSERT INTO sounds (
+                name, server_id, uploader_id, public, src, loudness, trim_start_ms, trim_end_ms,
+                created_at, duration_ms, size_bytes, cloned_from
+            )
+                SELECT ?, ?, ?, public, src, loudness, trim_start_ms, trim_end_ms, NOW(), duration_ms,
+                    size_bytes, id
+                FROM sounds
+                WHERE id = ?",
+            name,
+            server_id.into(),
+            user_id.into(),
+            self.id
+        )
+        .execute(db_pool)
+        .await?;
+
+        Ok(result.last_insert_id() as u32)
+    }
+
     pub async fn create_anon<G: Into<u64>, U: Into<u64>>(
         name: &str,
         src_url: &str,
         server_id: G,
         user_id: U,
         db_pool: impl Executor<'_, Database = Database>,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + Send>> {
-        let server_id = server_id.into();
-        let user_id = user_id.into();
+    ) -> Result<u32, Box<dyn std::error::Error + Send + Sync + Send>> {
+        match process_src(src_url).await {
+            Some(data) => Self::insert(name, data, server_id, user_id, db_pool).await,
 
-        async fn process_src(src_url: &str) -> Option<Vec<u8>> {
-            let output = Command::new("ffmpeg")
-                .kill_on_drop(true)
-                .arg("-i")
-                .arg(src_url)
-                .arg("-loglevel")
-                .arg("error")
-                .arg("-f")
-                .arg("opus")
-                .arg("-fs")
-                .arg(UPLOAD_MAX_SIZE.to_string())
-                .arg("pipe:1")
-                .output()
-                .await;
-
-            match output {
-                Ok(out) => {
-                    if out.status.success() {
-                        Some(out.stdout)
-                    } else {
-                        None
-                    }
-                }
+            None => Err(Box::new(ErrorTypes::InvalidFile)),
+        }
+    }
+
+    /// Create a sound from a file that has already been downloaded, such as one from a pack
+    pub async fn create_from_bytes<G: Into<u64>, U: Into<u64>>(
+        name: &str,
+        src: &[u8],
+        server_id: G,
+        user_id: U,
+        db_pool: impl Executor<'_, Database = Database>,
+    ) -> Result<u32, Box<dyn std::error::Error + Send + Sync + Send>> {
+        let max_size = UPLOAD_MAX_SIZE.to_string();
+        let output = ffmpeg::pipe(
+            src,
+            &[
+                "-loglevel",
+                "error",
+                "-f",
+                "opus",
+                "-fs",
+                &max_size,
+                "pipe:1",
+            ],
+        )
+        .await;
+
+        match output {
+            Some(output) => Self::insert(name, output.stdout, server_id, user_id, db_pool).await,
+
+            None => Err(Box::new(ErrorTypes::InvalidFile)),
+        }
+    }
+
+    async fn insert<G: Into<u64>, U: Into<u64>>(
+        name: &str,
+        data: Vec<u8>,
+        server_id: G,
+        user_id: U,
+        db_pool: impl Executor<'_, Database = Database>,
+    ) -> Result<u32, Box<dyn std::error::Error + Send + Sync + Send>> {
+        let loudness = measure_loudness(&data).await;
+        let duration_ms = probe_duration(data.clone()).map(|d| d.as_millis() as u32);
+        let size_bytes = data.len() as u32;
 
-                Err(_) => None,
+        let result = sqlx::query!(
+            "
+            INSERT INTO sounds (
+                name, server_id, uploader_id, public, src, loudness, created_at, duration_ms, size_bytes
+            )
+                VALUES (?, ?, ?, 1, ?, ?, NOW(), ?, ?)",
+            name,
+            server_id.into(),
+            user_id.into(),
+            data,
+            loudness,
+            duration_ms,
+            size_bytes
+        )
+        .execute(db_pool)
+        .await?;
+
+        Ok(result.last_insert_id() as u32)
+    }
+}
+
+/// Convert an uploaded file to Opus, limited to the maximum upload size
+async fn process_src(src_url: &str) -> Option<Vec<u8>> {
+    let output = Command::new("ffmpeg")
+        .kill_on_drop(true)
+        .arg("-i")
+        .arg(src_url)
+        .arg("-loglevel")
+        .arg("error")
+        .arg("-f")
+        .arg("opus")
+        .arg("-fs")
+        .arg(UPLOAD_MAX_SIZE.to_string())
+        .arg("pipe:1")
+        .output()
+        .await;
+
+    match output {
+        Ok(out) => {
+            if out.status.success() {
+                Some(out.stdout)
+            } else {
+                None
             }
         }
 
-        let source = process_src(src_url).await;
+        Err(_) => None,
+    }
+}
 
-        match source {
-            Some(data) => {
-                match sqlx::query!(
-                    "
-                    INSERT INTO sounds (name, server_id, uploader_id, public, src)
-                        VALUES (?, ?, ?, 1, ?)",
-                    name,
-                    server_id,
-                    user_id,
-                    data
-                )
-                .execute(db_pool)
-                .await
-                {
-                    Ok(_) => Ok(()),
+/// Order in which sounds are listed
+#[derive(Copy, Clone, Default, Serialize, Deserialize, PartialEq, poise::ChoiceParameter)]
+pub enum SoundSort {
+    #[default]
+    #[name = "Newest"]
+    #[serde(rename = "new")]
+    Newest = 0,
+    #[name = "Name"]
+    #[serde(rename = "name")]
+    Name = 1,
+    #[name = "Oldest"]
+    #[serde(rename = "old")]
+    Oldest = 2,
+    #[name = "Most played"]
+    #[serde(rename = "plays")]
+    MostPlayed = 3,
+}
+
+/// Restrictions on the sounds returned by a search
+#[derive(Clone, Default)]
+pub struct SearchFilters {
+    pub uploader_id: Option<u64>,
+    pub server_id: Option<u64>,
+    pub public: Option<bool>,
+    pub min_duration_ms: Option<u32>,
+    pub max_duration_ms: Option<u32>,
+    pub favorites_only: bool,
+}
+
+/// A single play of a sound, from the play history
+pub struct SoundPlay {
+    /// Unset once the sound has been permanently deleted
+    pub sound_id: Option<u32>,
+    /// Name of the sound when it was played
+    pub name: String,
+    /// Whether the sound is in the trash or deleted, and can't be played again
+    pub trashed: bool,
+    pub user_id: u64,
+    pub channel_id: Option<u64>,
+    pub trigger_type: String,
+    pub played_at: i64,
+}
+
+/// A sound and how many times it has been played
+pub struct TopSound {
+    pub id: u32,
+    pub name: String,
+    pub plays: i64,
+}
+
+/// Details of a sound that aren't needed to play it
+pub struct SoundDetails {
+    /// Unix timestamp of the upload. Unknown for sounds uploaded before it was recorded
+    pub created_at: Option<i64>,
+    pub duration_ms: Option<u32>,
+    pub size_bytes: Option<u32>,
+    pub plays: i64,
+    pub favorites: i64,
+    /// ID of the sound this was cloned from
+    pub cloned_from: Option<u32>,
+}
+
+/// Read the duration of some audio from its container
+fn probe_duration(src: Vec<u8>) -> Option<Duration> {
+    let stream = MediaSourceStream::new(Box::new(Cursor::new(src)), Default::default());
+    let probed = symphonia::default::get_probe()
+        .format(
+            &Hint::new(),
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .ok()?;
 
-                    Err(e) => Err(Box::new(e)),
+    let params = &probed.format.default_track()?.codec_params;
+    let time = params.time_base?.calc_time(params.n_frames?);
+
+    Some(Duration::from_secs(time.seconds) + Duration::from_secs_f64(time.frac))
+}
+
+/// Measure the integrated loudness (EBU R128) of some audio, in LUFS
+async fn measure_loudness(src: &[u8]) -> Option<f32> {
+    let output = ffmpeg::pipe(
+        src,
+        &["-af", "loudnorm=print_format=json", "-f", "null", "-"],
+    )
+    .await?;
+
+    // loudnorm prints its measurements as a JSON object at the end of the log
+    let log = String::from_utf8_lossy(&output.stderr);
+    let measurements: serde_json::Value = serde_json::from_str(&log[log.rfind('{')?..]).ok()?;
+
+    measurements["input_i"]
+        .as_str()?
+        .parse::<f32>()
+        .ok()
+        .filter(|loudness| loudness.is_finite())
+}
+
+/// Normalise a tag to the form it is stored in, or `None` if it isn't a valid tag
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().trim_start_matches('#').to_lowercase();
+
+    if !tag.is_empty()
+        && tag.chars().count() <= MAX_TAG_LENGTH
+        && tag
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        Some(tag)
+    } else {
+        None
+    }
+}
+
+/// Split a search query into the sound name and any `#tag` filters in it
+fn parse_query(query: &str) -> (String, Vec<String>) {
+    let mut name = vec![];
+    let mut tags = vec![];
+
+    for word in query.split_whitespace() {
+        match word.strip_prefix('#').and_then(normalize_tag) {
+            Some(tag) => {
+                if !tags.contains(&tag) {
+                    tags.push(tag);
                 }
             }
 
-            None => Err(Box::new(ErrorTypes::InvalidFile)),
+            None => name.push(word),
         }
     }
+
+    // Leave queries without tags untouched, so names with unusual spacing still match
+    if tags.is_empty() {
+        (query.to_string(), tags)
+    } else {
+        (name.join(" "), tags)
+    }
 }
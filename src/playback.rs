@@ -0,0 +1,467 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use dashmap::DashMap;
+use log::warn;
+use poise::serenity_prelude::{async_trait, prelude::TypeMapKey, ChannelId, GuildId, UserId};
+use songbird::{
+    events::{Event, EventContext, EventHandler, TrackEvent},
+    tracks::{LoopState, TrackHandle, TrackResult},
+    Songbird,
+};
+use sqlx::Pool;
+use tokio::sync::RwLock;
+
+use crate::{
+    effects::Effects,
+    models::{guild_data::GuildData, sound::Sound},
+    Database,
+};
+
+/// What caused a sound to be played, as recorded in the play history
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum PlayTrigger {
+    #[default]
+    Command,
+    Soundboard,
+    Greet,
+    Random,
+}
+
+impl PlayTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlayTrigger::Command => "command",
+            PlayTrigger::Soundboard => "soundboard",
+            PlayTrigger::Greet => "greet",
+            PlayTrigger::Random => "random",
+        }
+    }
+}
+
+/// Details of the sound behind a track, stored in the track's typemap
+#[derive(Clone)]
+pub struct TrackMetadata {
+    pub sound: Sound,
+    pub requester: UserId,
+    pub looping: bool,
+    pub trigger: PlayTrigger,
+    /// Voice channel the track was played in, for the play history
+    pub channel: Option<ChannelId>,
+    pub effects: Effects,
+}
+
+impl TypeMapKey for TrackMetadata {
+    type Value = TrackMetadata;
+}
+
+impl TrackMetadata {
+    pub async fn attach(self, handle: &TrackHandle) {
+        handle.typemap().write().await.insert::<TrackMetadata>(self);
+    }
+
+    pub async fn of(handle: &TrackHandle) -> Option<TrackMetadata> {
+        handle
+            .typemap()
+            .read()
+            .await
+            .get::<TrackMetadata>()
+            .cloned()
+    }
+}
+
+/// A track that is currently playing in a guild
+#[derive(Clone)]
+pub struct NowPlaying {
+    pub handle: TrackHandle,
+    pub metadata: TrackMetadata,
+    pub started_at: Instant,
+}
+
+/// Tracks currently playing in each guild, kept up to date by songbird track events
+#[derive(Clone)]
+pub struct PlaybackRegistry {
+    guilds: Arc<DashMap<GuildId, Vec<NowPlaying>>>,
+    idle_since: Arc<DashMap<GuildId, Instant>>,
+    songbird: Arc<Songbird>,
+    database: Pool<Database>,
+}
+
+impl PlaybackRegistry {
+    pub fn new(songbird: Arc<Songbird>, database: Pool<Database>) -> Self {
+        Self {
+            guilds: Default::default(),
+            idle_since: Default::default(),
+            songbird,
+            database,
+        }
+    }
+
+    /// Subscribe to a track's events so it is registered when it starts and removed when it ends.
+    /// If the guild has nothing left playing once the track ends, the bot leaves after the guild's
+    /// idle timeout, as set at that point
+    pub fn watch(
+        &self,
+        guild_id: GuildId,
+        handle: &TrackHandle,
+        metadata: TrackMetadata,
+        guild_data: Arc<RwLock<GuildData>>,
+    ) -> TrackResult<()> {
+        handle.add_event(
+            Event::Track(TrackEvent::Play),
+            TrackStarted {
+                registry: self.clone(),
+                guild_id,
+                metadata,
+            },
+        )?;
+        handle.add_event(
+            Event::Track(TrackEvent::End),
+            TrackEnded {
+                registry: self.clone(),
+                guild_id,
+                guild_data,
+            },
+        )?;
+
+        Ok(())
+    }
+
+    pub fn started(&self, guild_id: GuildId, handle: &TrackHandle, metadata: TrackMetadata) {
+        self.idle_since.remove(&guild_id);
+
+        let mut playing = self.guilds.entry(guild_id).or_default();
+
+        // Resuming a paused track fires the same event as starting it
+        if !playing.iter().any(|p| p.handle.uuid() == handle.uuid()) {
+            playing.push(NowPlaying {
+                handle: handle.clone(),
+                metadata: metadata.clone(),
+                started_at: Instant::now(),
+            });
+
+            self.record_play(guild_id, metadata);
+        }
+    }
+
+    /// Add a track that has started to the play history and play counts
+    fn record_play(&self, guild_id: GuildId, metadata: TrackMetadata) {
+        let database = self.database.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = metadata
+                .sound
+                .log_play(
+                    guild_id,
+                    metadata.channel.map(|channel| channel.get()),
+                    metadata.requester,
+                    metadata.trigger,
+                    &database,
+                )
+                .await
+            {
+                warn!(
+                    "Couldn't record play of sound {}: {:?}",
+                    metadata.sound.id, e
+                );
+            }
+        });
+    }
+
+    pub fn ended(&self, guild_id: GuildId, handle: &TrackHandle) {
+        if let Some(mut playing) = self.guilds.get_mut(&guild_id) {
+            playing.retain(|p| p.handle.uuid() != handle.uuid());
+        }
+    }
+
+    pub fn clear(&self, guild_id: GuildId) {
+        self.guilds.remove(&guild_id);
+        self.idle_since.remove(&guild_id);
+    }
+
+    /// Leave the guild's call after `timeout`, unless something plays in the meantime
+    fn start_idle_timer(&self, guild_id: GuildId, timeout: Duration) {
+        if !self.playing(guild_id).is_empty() {
+            return;
+        }
+
+        let idle_since = Instant::now();
+        self.idle_since.insert(guild_id, idle_since);
+
+        let registry = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(timeout).await;
+
+            // A newer timer replaces this one, and starting a track cancels it
+            if registry
+                .idle_since
+                .remove_if(&guild_id, |_, since| *since == idle_since)
+                .is_some()
+            {
+                let _ = registry.songbird.remove(guild_id).await;
+                registry.clear(guild_id);
+            }
+        });
+    }
+
+    pub fn playing(&self, guild_id: GuildId) -> Vec<NowPlaying> {
+        self.guilds
+            .get(&guild_id)
+            .map(|playing| playing.clone())
+            .unwrap_or_default()
+    }
+
+    /// The most recently started track in a guild
+    pub fn now_playing(&self, guild_id: GuildId) -> Option<NowPlaying> {
+        self.guilds
+            .get(&guild_id)
+            .and_then(|playing| playing.last().cloned())
+    }
+}
+
+struct TrackStarted {
+    registry: PlaybackRegistry,
+    guild_id: GuildId,
+    metadata: TrackMetadata,
+}
+
+#[async_trait]
+impl EventHandler for TrackStarted {
+    async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+        if let EventContext::Track(tracks) = ctx {
+            for (_, handle) in tracks.iter() {
+                self.registry
+                    .started(self.guild_id, handle, self.metadata.clone());
+            }
+        }
+
+        None
+    }
+}
+
+struct TrackEnded {
+    registry: PlaybackRegistry,
+    guild_id: GuildId,
+    guild_data: Arc<RwLock<GuildData>>,
+}
+
+#[async_trait]
+impl EventHandler for TrackEnded {
+    async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+        if let EventContext::Track(tracks) = ctx {
+            for (_, handle) in tracks.iter() {
+                self.registry.ended(self.guild_id, handle);
+            }
+
+            let idle_timeout = self.guild_data.read().await.idle_timeout;
+            if idle_timeout > 0 {
+                self.registry
+                    .start_idle_timer(self.guild_id, Duration::from_secs(idle_timeout as u64));
+            }
+        }
+
+        None
+    }
+}
+
+/// Stops a track when its event fires
+pub struct StopTrack;
+
+#[async_trait]
+impl EventHandler for StopTrack {
+    async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+        if let EventContext::Track(tracks) = ctx {
+            for (_, handle) in tracks.iter() {
+                let _ = handle.stop();
+            }
+        }
+
+        None
+    }
+}
+
+/// How often a track with an end trim point checks its position
+pub const TRIM_CHECK_PERIOD: Duration = Duration::from_millis(20);
+
+/// Skips a track past its start trim point when it becomes playable or loops
+pub struct TrimStart {
+    pub start: Duration,
+}
+
+#[async_trait]
+impl EventHandler for TrimStart {
+    async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+        if let EventContext::Track(tracks) = ctx {
+            for (_, handle) in tracks.iter() {
+                drop(handle.seek(self.start));
+            }
+        }
+
+        None
+    }
+}
+
+/// Ends a track, or restarts it from its start trim point if looping, once it passes its end
+/// trim point
+pub struct TrimEnd {
+    pub start: Duration,
+    pub end: Duration,
+}
+
+#[async_trait]
+impl EventHandler for TrimEnd {
+    async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+        if let EventContext::Track(tracks) = ctx {
+            for (state, handle) in tracks.iter() {
+                if state.position < self.end {
+                    continue;
+                }
+
+                match state.loops {
+                    LoopState::Infinite => {
+                        drop(handle.seek(self.start));
+                    }
+                    LoopState::Finite(0) => {
+                        let _ = handle.stop();
+                    }
+                    LoopState::Finite(n) => {
+                        let _ = handle.loop_for(n - 1);
+                        drop(handle.seek(self.start));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Interval between volume changes while fading
+const FADE_STEP: Duration = Duration::from_millis(20);
+
+/// Flag in a track's typemap that stops its fade in, set when it starts fading out
+struct FadeInCancelled;
+
+impl TypeMapKey for FadeInCancelled {
+    type Value = Arc<AtomicBool>;
+}
+
+/// Raises a track from silence once it starts playing
+#[derive(Clone)]
+struct FadeIn {
+    volume: f32,
+    duration: Duration,
+    started: Arc<AtomicBool>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl FadeIn {
+    fn start(&self, handle: &TrackHandle) {
+        // Both the play event and `fade_in` itself may start the fade, but only one ramp runs
+        if self.started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let handle = handle.clone();
+        let fade = self.clone();
+        tokio::spawn(async move {
+            ramp_volume(
+                &handle,
+                0.0,
+                fade.volume,
+                fade.duration,
+                Some(&fade.cancelled),
+            )
+            .await
+        });
+    }
+}
+
+#[async_trait]
+impl EventHandler for FadeIn {
+    async fn act(&self, ctx: &EventContext<'_>) -> Option<Event> {
+        if let EventContext::Track(tracks) = ctx {
+            for (_, handle) in tracks.iter() {
+                self.start(handle);
+            }
+        }
+
+        // Resuming a paused track fires the play event again, which shouldn't restart the fade
+        Some(Event::Cancel)
+    }
+}
+
+/// Start a track silent and raise it to `volume` over `duration` once it starts playing. Pass
+/// `playing` for tracks that have already started, whose play event may have been missed
+pub async fn fade_in(
+    handle: &TrackHandle,
+    volume: f32,
+    duration: Duration,
+    playing: bool,
+) -> TrackResult<()> {
+    handle.set_volume(0.0)?;
+
+    let fade = FadeIn {
+        volume,
+        duration,
+        started: Default::default(),
+        cancelled: Default::default(),
+    };
+
+    handle
+        .typemap()
+        .write()
+        .await
+        .insert::<FadeInCancelled>(fade.cancelled.clone());
+    handle.add_event(Event::Track(TrackEvent::Play), fade.clone())?;
+
+    if playing {
+        fade.start(handle);
+    }
+
+    Ok(())
+}
+
+/// Lower a track to silence over `duration`, then stop it
+pub fn fade_out(handle: TrackHandle, duration: Duration) {
+    tokio::spawn(async move {
+        if let Some(cancelled) = handle.typemap().read().await.get::<FadeInCancelled>() {
+            cancelled.store(true, Ordering::SeqCst);
+        }
+
+        if let Ok(info) = handle.get_info().await {
+            let _ = ramp_volume(&handle, info.volume, 0.0, duration, None).await;
+        }
+
+        let _ = handle.stop();
+    });
+}
+
+/// Change a track's volume gradually, stopping early if `cancelled` is set
+async fn ramp_volume(
+    handle: &TrackHandle,
+    from: f32,
+    to: f32,
+    duration: Duration,
+    cancelled: Option<&AtomicBool>,
+) -> TrackResult<()> {
+    let steps = (duration.as_millis() / FADE_STEP.as_millis()).max(1) as u32;
+    let mut interval = tokio::time::interval(FADE_STEP);
+
+    for step in 1..=steps {
+        interval.tick().await;
+
+        if cancelled.is_some_and(|cancelled| cancelled.load(Ordering::SeqCst)) {
+            break;
+        }
+
+        handle.set_volume(from + (to - from) * step as f32 / steps as f32)?;
+    }
+
+    Ok(())
+}
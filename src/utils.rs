@@ -1,5 +1,6 @@
-use std::{ops::Deref, sync::Arc};
+use std::{ops::Deref, sync::Arc, time::Duration};
 
+use log::warn;
 use poise::serenity_prelude::{
     model::{
         guild::Guild,
@@ -7,50 +8,222 @@ use poise::serenity_prelude::{
     },
     ChannelType, EditVoiceState, GuildId,
 };
-use songbird::{tracks::TrackHandle, Call};
-use sqlx::Executor;
+use songbird::{events::Event, tracks::TrackHandle, Call};
 use tokio::sync::{Mutex, MutexGuard};
 
 use crate::{
+    effects::Effects,
     models::{
-        guild_data::CtxGuildData,
+        guild_data::{CtxGuildData, PlaybackPolicy},
         sound::{Sound, SoundCtx},
     },
-    Data, Database,
+    playback::{fade_in, fade_out, PlayTrigger, StopTrack, TrackMetadata},
+    Data,
 };
 
+/// How a sound should be played
+#[derive(Clone, Copy, Default)]
+pub struct PlayOptions {
+    pub r#loop: bool,
+    pub effects: Effects,
+    pub trigger: PlayTrigger,
+}
+
 pub async fn play_audio(
     sound: &Sound,
     volume: u8,
-    call_handler: &mut MutexGuard<'_, Call>,
-    db_pool: impl Executor<'_, Database = Database>,
-    r#loop: bool,
+    call: &Mutex<Call>,
+    data: &Data,
+    guild_id: GuildId,
+    requester: UserId,
+    options: PlayOptions,
 ) -> Result<TrackHandle, Box<dyn std::error::Error + Send + Sync>> {
-    let track = sound.playable(db_pool).await?;
-    let handle = call_handler.play_input(track);
+    let (policy, fade_in_ms) = {
+        let guild_data = data.guild_data(guild_id).await?;
+        let read = guild_data.read().await;
 
-    handle.set_volume(volume as f32 / 100.0)?;
+        (read.playback_policy, read.fade_in_ms)
+    };
+
+    // Rendering effects can take a while, so the call is only locked once the track is ready
+    let track = sound.playable(&data.database, &options.effects).await?;
+    let call_handler = &mut call.lock().await;
+
+    let handle = match policy {
+        PlaybackPolicy::Overlay => call_handler.play(track),
+        PlaybackPolicy::Interrupt => {
+            call_handler.queue().stop();
+            call_handler.stop();
+
+            call_handler.play(track)
+        }
+        PlaybackPolicy::Enqueue => call_handler.enqueue(track).await,
+    };
 
-    if r#loop {
+    let volume = volume as f32 / 100.0 * sound.gain();
+    if fade_in_ms > 0 {
+        fade_in(
+            &handle,
+            volume,
+            Duration::from_millis(fade_in_ms as u64),
+            !is_waiting(call_handler, &handle),
+        )
+        .await?;
+    } else {
+        handle.set_volume(volume)?;
+    }
+
+    if options.r#loop {
         handle.enable_loop()?;
     } else {
         handle.disable_loop()?;
     }
 
+    track_playback(
+        &handle,
+        TrackMetadata {
+            sound: sound.clone(),
+            requester,
+            looping: options.r#loop,
+            trigger: options.trigger,
+            channel: current_channel(call_handler),
+            effects: options.effects,
+        },
+        call_handler,
+        data,
+        guild_id,
+    )
+    .await?;
+
     Ok(handle)
 }
 
 pub async fn queue_audio(
     sounds: &[Sound],
     volume: u8,
-    call_handler: &mut MutexGuard<'_, Call>,
-    db_pool: impl Executor<'_, Database = Database> + Copy,
+    call: &Mutex<Call>,
+    data: &Data,
+    guild_id: GuildId,
+    requester: UserId,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let fade_in_ms = data.guild_data(guild_id).await?.read().await.fade_in_ms;
+
+    let mut tracks = vec![];
     for sound in sounds {
-        let track = sound.playable(db_pool).await?;
-        let handle = call_handler.enqueue_input(track).await;
+        tracks.push(sound.playable(&data.database, &Effects::default()).await?);
+    }
+
+    let call_handler = &mut call.lock().await;
+
+    for (sound, track) in sounds.iter().zip(tracks) {
+        let handle = call_handler.enqueue(track).await;
+
+        let volume = volume as f32 / 100.0 * sound.gain();
+        if fade_in_ms > 0 {
+            fade_in(
+                &handle,
+                volume,
+                Duration::from_millis(fade_in_ms as u64),
+                !is_waiting(call_handler, &handle),
+            )
+            .await?;
+        } else {
+            handle.set_volume(volume)?;
+        }
 
-        handle.set_volume(volume as f32 / 100.0)?;
+        track_playback(
+            &handle,
+            TrackMetadata {
+                sound: sound.clone(),
+                requester,
+                looping: false,
+                trigger: PlayTrigger::Command,
+                channel: current_channel(call_handler),
+                effects: Effects::default(),
+            },
+            call_handler,
+            data,
+            guild_id,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn track_playback(
+    handle: &TrackHandle,
+    metadata: TrackMetadata,
+    call_handler: &mut MutexGuard<'_, Call>,
+    data: &Data,
+    guild_id: GuildId,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let guild_data = data.guild_data(guild_id).await?;
+    let max_duration = guild_data.read().await.max_duration;
+
+    if let Some(max_duration) = max_duration {
+        // Play time keeps counting across loops, so this also limits looped sounds
+        handle.add_event(
+            Event::Delayed(Duration::from_secs(max_duration as u64)),
+            StopTrack,
+        )?;
+    }
+
+    data.playback
+        .watch(guild_id, handle, metadata.clone(), guild_data)?;
+
+    // Tracks waiting in the queue are registered by their play event once they start
+    if !is_waiting(call_handler, handle) {
+        data.playback.started(guild_id, handle, metadata.clone());
+    }
+
+    metadata.attach(handle).await;
+
+    Ok(())
+}
+
+fn current_channel(call_handler: &Call) -> Option<ChannelId> {
+    call_handler
+        .current_channel()
+        .map(|channel| ChannelId::new(channel.0.get()))
+}
+
+/// Whether a track is queued behind another, rather than already playing
+fn is_waiting(call_handler: &Call, handle: &TrackHandle) -> bool {
+    call_handler
+        .queue()
+        .current_queue()
+        .iter()
+        .skip(1)
+        .any(|h| h.uuid() == handle.uuid())
+}
+
+/// Stop everything playing in a guild, fading out if the guild has a fade out set
+pub async fn stop_playback(
+    call: &Mutex<Call>,
+    data: &Data,
+    guild_id: GuildId,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let fade_out_ms = data.guild_data(guild_id).await?.read().await.fade_out_ms;
+
+    if fade_out_ms == 0 {
+        call.lock().await.stop();
+    } else {
+        // Empty the queue first so that nothing starts while the current sounds fade
+        call.lock().await.queue().modify_queue(|queue| {
+            if queue.len() > 1 {
+                for queued in queue.drain(1..) {
+                    let _ = queued.stop();
+                }
+            }
+        });
+
+        for now_playing in data.playback.playing(guild_id) {
+            fade_out(
+                now_playing.handle,
+                Duration::from_millis(fade_out_ms as u64),
+            );
+        }
     }
 
     Ok(())
@@ -110,7 +283,7 @@ pub async fn play_from_query(
     user_id: UserId,
     channel: Option<ChannelId>,
     query: &str,
-    r#loop: bool,
+    options: PlayOptions,
 ) -> String {
     let guild_id = guild.deref().id;
 
@@ -124,34 +297,43 @@ pub async fn play_from_query(
 
     match channel_to_join {
         Some(user_channel) => {
-            let mut sound_vec = data
-                .search_for_sound(query, guild_id, user_id, true)
-                .await
-                .unwrap();
+            let mut sound_vec = match data.search_for_sound(query, guild_id, user_id, true).await {
+                Ok(sounds) => sounds,
+                Err(e) => return format!("Couldn't search for sound: {}", e),
+            };
+
+            // Fall back to the closest match when no sound has exactly the name given
+            let closest_match = sound_vec.is_empty() && !query.trim().is_empty();
+            if closest_match {
+                sound_vec = match data.search_for_sound(query, guild_id, user_id, false).await {
+                    Ok(sounds) => sounds,
+                    Err(e) => return format!("Couldn't search for sound: {}", e),
+                };
+            }
 
             let sound_res = sound_vec.first_mut();
 
             match sound_res {
                 Some(sound) => {
-                    {
-                        let call_handler = join_channel(ctx, guild_id, user_channel).await.unwrap();
-
-                        let guild_data = data.guild_data(guild_id).await.unwrap();
-
-                        let mut lock = call_handler.lock().await;
-
-                        play_audio(
-                            sound,
-                            guild_data.read().await.volume,
-                            &mut lock,
-                            &data.database,
-                            r#loop,
-                        )
-                        .await
-                        .unwrap();
+                    let played = async {
+                        let call = join_channel(ctx, guild_id, user_channel).await?;
+                        let volume = data.guild_data(guild_id).await?.read().await.volume;
+
+                        play_audio(sound, volume, &call, data, guild_id, user_id, options).await
+                    }
+                    .await;
+
+                    if let Err(e) = played {
+                        warn!("Couldn't play sound {}: {:?}", sound.id, e);
+
+                        return format!("Couldn't play sound: {}", e);
                     }
 
-                    format!("Playing sound {} with ID {}", sound.name, sound.id)
+                    if closest_match {
+                        format!("Playing closest match {} with ID {}", sound.name, sound.id)
+                    } else {
+                        format!("Playing sound {} with ID {}", sound.name, sound.id)
+                    }
                 }
 
                 None => "Couldn't find sound by term provided".to_string(),
@@ -161,3 +343,9 @@ pub async fn play_from_query(
         None => "You are not in a voice chat!".to_string(),
     }
 }
+
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}